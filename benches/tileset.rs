@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use pax_romana::tileset::Tileset;
+use pax_romana::xmlelements::XMLElements;
+
+// a 2048x2048 image of 16x16 tiles, every tile with properties and every tenth animated,
+// entity tiles make every tile's clip depend on its entity's keyframes
+fn tileset(entities: bool) -> String {
+    let columns = 2048 / 16;
+    let count = columns * columns;
+
+    let tiles: String = (0..count)
+        .map(|id| {
+            let animation = if id % 10 == 0 {
+                format!(
+                    r#"<animation><frame tileid="{}" duration="100"/><frame tileid="{}" duration="100"/></animation>"#,
+                    id,
+                    (id + 1) % count
+                )
+            } else {
+                String::new()
+            };
+            let entity = if entities {
+                format!(
+                    r#"<property name="entity" value="entity-{}"/><property name="keyframe" type="int" value="{}"/>"#,
+                    id / 4,
+                    id % 4
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                r#"<tile id="{}"><properties><property name="solid" type="bool" value="{}"/>{}</properties>{}</tile>"#,
+                id,
+                id % 2 == 0,
+                entity,
+                animation
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<tileset tilewidth="16" tileheight="16" tilecount="{}" columns="{}"><image source="tileset.png" width="2048" height="2048"/>{}</tileset>"#,
+        count, columns, tiles
+    )
+}
+
+fn load(c: &mut Criterion) {
+    let tileset = self::tileset(true);
+    let plain = self::tileset(false);
+    let count = (2048 / 16) * (2048 / 16);
+
+    let mut group = c.benchmark_group("tileset");
+    group.sample_size(10);
+    group.bench_function("parse 2048x2048", |b| {
+        b.iter(|| XMLElements::new(tileset.as_bytes()).unwrap())
+    });
+    group.bench_function("load 2048x2048", |b| {
+        b.iter(|| Tileset::new(tileset.as_bytes()).unwrap())
+    });
+    group.bench_function("load 2048x2048 without entities", |b| {
+        b.iter(|| Tileset::new(plain.as_bytes()).unwrap())
+    });
+
+    // a map that uses every tile builds every timeline
+    let loaded = Tileset::new(tileset.as_bytes()).unwrap();
+    group.bench_function("use every tile of 2048x2048", |b| {
+        b.iter_batched(
+            || loaded.clone(),
+            |mut tileset| tileset.give_used_tiles(1..=count).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, load);
+criterion_main!(benches);
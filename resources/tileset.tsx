@@ -1,13 +1,30 @@
 <?xml version="1.0" encoding="UTF-8"?>
-<tileset version="1.2" tiledversion="1.2.1" name="tileset" tilewidth="16" tileheight="16" tilecount="50" columns="10">
- <image source="tileset.png" width="160" height="80"/>
+<tileset version="1.2" tiledversion="1.2.1" name="tileset" tilewidth="16" tileheight="16" tilecount="90" columns="10">
+ <image source="tileset.png" width="160" height="144"/>
+ <tile id="2">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="10">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="11">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
  <tile id="14">
   <properties>
-   <property name="delay" type="int" value="100"/>
    <property name="scramble_delay" type="bool" value="true"/>
    <property name="entity" value="flame"/>
-   <property name="keyframe" type="int" value="0"/>
   </properties>
+  <animation>
+   <frame tileid="14" duration="100"/>
+   <frame tileid="24" duration="100"/>
+  </animation>
  </tile>
  <tile id="15">
   <properties>
@@ -15,12 +32,19 @@
    <property name="visible" type="bool" value="false"/>
   </properties>
  </tile>
+ <tile id="20">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="21">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
  <tile id="24">
   <properties>
-   <property name="delay" type="int" value="100"/>
-   <property name="scramble_delay" type="bool" value="true"/>
    <property name="entity" value="flame"/>
-   <property name="keyframe" type="int" value="1"/>
   </properties>
  </tile>
  <tile id="25">
@@ -29,18 +53,86 @@
    <property name="visible" type="bool" value="false"/>
   </properties>
  </tile>
+ <tile id="30">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="31">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="32">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="33">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
  <tile id="34">
   <properties>
-   <property name="delay" type="int" value="100"/>
-   <property name="entity" value="player-top"/>
-   <property name="keyframe" type="int" value="0"/>
+   <property name="entity" value="player-left"/>
   </properties>
+  <animation>
+   <frame tileid="34" duration="100"/>
+   <frame tileid="35" duration="100"/>
+  </animation>
  </tile>
  <tile id="35">
   <properties>
-   <property name="delay" type="int" value="100"/>
-   <property name="entity" value="player-top"/>
-   <property name="keyframe" type="int" value="1"/>
+   <property name="entity" value="player-left"/>
+  </properties>
+ </tile>
+ <tile id="36">
+  <properties>
+   <property name="entity" value="peasant-left"/>
+  </properties>
+  <animation>
+   <frame tileid="36" duration="100"/>
+   <frame tileid="37" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="37">
+  <properties>
+   <property name="entity" value="peasant-left"/>
+  </properties>
+ </tile>
+ <tile id="38">
+  <properties>
+   <property name="entity" value="soldier-left"/>
+  </properties>
+  <animation>
+   <frame tileid="38" duration="100"/>
+   <frame tileid="39" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="39">
+  <properties>
+   <property name="entity" value="soldier-left"/>
+  </properties>
+ </tile>
+ <tile id="40">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="41">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="42">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="43">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
   </properties>
  </tile>
  <tile id="44">
@@ -57,4 +149,116 @@
    <property name="keyframe" type="int" value="1"/>
   </properties>
  </tile>
+ <tile id="46">
+  <properties>
+   <property name="delay" type="int" value="100"/>
+   <property name="entity" value="peasant-bottom"/>
+   <property name="keyframe" type="int" value="0"/>
+  </properties>
+ </tile>
+ <tile id="47">
+  <properties>
+   <property name="delay" type="int" value="100"/>
+   <property name="entity" value="peasant-bottom"/>
+   <property name="keyframe" type="int" value="1"/>
+  </properties>
+ </tile>
+ <tile id="48">
+  <properties>
+   <property name="delay" type="int" value="100"/>
+   <property name="entity" value="soldier-bottom"/>
+   <property name="keyframe" type="int" value="0"/>
+  </properties>
+ </tile>
+ <tile id="49">
+  <properties>
+   <property name="delay" type="int" value="100"/>
+   <property name="entity" value="soldier-bottom"/>
+   <property name="keyframe" type="int" value="1"/>
+  </properties>
+ </tile>
+ <tile id="50">
+  <properties>
+   <property name="entity" value="player-down"/>
+  </properties>
+  <animation>
+   <frame tileid="50" duration="100"/>
+   <frame tileid="51" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="51">
+  <properties>
+   <property name="entity" value="player-down"/>
+  </properties>
+ </tile>
+ <tile id="52">
+  <properties>
+   <property name="entity" value="peasant-down"/>
+  </properties>
+  <animation>
+   <frame tileid="52" duration="100"/>
+   <frame tileid="53" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="53">
+  <properties>
+   <property name="entity" value="peasant-down"/>
+  </properties>
+ </tile>
+ <tile id="54">
+  <properties>
+   <property name="entity" value="soldier-down"/>
+  </properties>
+  <animation>
+   <frame tileid="54" duration="100"/>
+   <frame tileid="55" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="55">
+  <properties>
+   <property name="entity" value="soldier-down"/>
+  </properties>
+ </tile>
+ <tile id="70">
+  <properties>
+   <property name="entity" value="player-up"/>
+  </properties>
+  <animation>
+   <frame tileid="70" duration="100"/>
+   <frame tileid="71" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="71">
+  <properties>
+   <property name="entity" value="player-up"/>
+  </properties>
+ </tile>
+ <tile id="72">
+  <properties>
+   <property name="entity" value="peasant-up"/>
+  </properties>
+  <animation>
+   <frame tileid="72" duration="100"/>
+   <frame tileid="73" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="73">
+  <properties>
+   <property name="entity" value="peasant-up"/>
+  </properties>
+ </tile>
+ <tile id="74">
+  <properties>
+   <property name="entity" value="soldier-up"/>
+  </properties>
+  <animation>
+   <frame tileid="74" duration="100"/>
+   <frame tileid="75" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="75">
+  <properties>
+   <property name="entity" value="soldier-up"/>
+  </properties>
+ </tile>
 </tileset>
@@ -1,107 +1,383 @@
 use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::nalgebra::Point2;
-use rand::Rng;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::str::FromStr;
 
-use crate::entity::Action;
+use crate::entity::{Action, Direction};
+use crate::error::Error;
 use crate::tile::{flip, Tile};
 use crate::tileset::Tileset;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Loop,
+    Once,
+    PingPong,
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Mode, ()> {
+        match value {
+            "loop" => Ok(Mode::Loop),
+            "once" => Ok(Mode::Once),
+            "ping_pong" => Ok(Mode::PingPong),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub tile: Tile,
+    pub duration: f32,
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub mode: Mode,
+    pub scramble: bool,
+}
+
+impl Clip {
+    pub fn new(frames: Vec<Frame>, mode: Mode) -> Clip {
+        Clip {
+            frames,
+            mode,
+            scramble: false,
+        }
+    }
+
+    // a single frame that is shown forever
+    pub fn still(tile: Tile) -> Clip {
+        Clip::new(
+            vec![Frame {
+                tile,
+                duration: 0.0,
+                event: None,
+            }],
+            Mode::Loop,
+        )
+    }
+
+    pub fn map_tiles(mut self, f: impl Fn(Tile) -> Tile) -> Clip {
+        for frame in self.frames.iter_mut() {
+            frame.tile = f(frame.tile.clone());
+        }
+        self
+    }
+
+    // the frames of one cycle, ping-pong clips play back without repeating their ends
+    fn get_order(&self) -> impl Iterator<Item = usize> {
+        let count = self.frames.len();
+        let back = match self.mode {
+            Mode::PingPong => 1..count.saturating_sub(1),
+            _ => 0..0,
+        };
+        (0..count).chain(back.rev())
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        self.get_order().map(|i| self.frames[i].duration).sum()
+    }
+
+    // the frame shown after playing for the given time
+    pub fn get_frame_at(&self, time: f32) -> usize {
+        let duration = self.get_duration();
+        let mut time = match self.mode {
+            Mode::Loop | Mode::PingPong if duration > 0.0 => time % duration,
+            _ => time,
+        };
+
+        let mut frame = 0;
+        for i in self.get_order() {
+            frame = i;
+            let duration = self.frames[i].duration;
+            if duration <= 0.0 || time < duration {
+                break;
+            }
+            time -= duration;
+        }
+        frame
+    }
+}
+
+// a clip played by elapsed time alone, so any number of cells can share it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    clip: Clip,
+    duration: f32,
+    time: f32,
+}
+
+impl Timeline {
+    pub fn new(clip: Clip) -> Timeline {
+        Timeline {
+            duration: clip.get_duration(),
+            clip,
+            time: 0.0,
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.clip.frames.len() > 1
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        // keeps the time precise, a full cycle later every frame is shown again
+        if self.clip.mode != Mode::Once && self.duration > 0.0 {
+            self.time %= self.duration;
+        }
+    }
+
+    // scrambled clips start each cell at its own point of the cycle
+    pub fn get_frame(&self, phase: f32) -> usize {
+        let offset = if self.clip.scramble {
+            phase * self.duration
+        } else {
+            0.0
+        };
+        self.clip.get_frame_at(self.time + offset)
+    }
+
+    pub fn get_tile(&self, phase: f32) -> &Tile {
+        &self.clip.frames[self.get_frame(phase)].tile
+    }
+
+    pub fn draw(&self, spritebatch: &mut SpriteBatch, position: Point2<f32>, phase: f32) {
+        self.get_tile(phase).draw(spritebatch, position);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Animation {
-    frames: Vec<Tile>,
-    timer: Instant,
-    current: Tile,
+    clip: Clip,
+    frame: usize,
+    timer: f32,
+    duration: f32,
+    started: bool,
+    reversing: bool,
+    finished: bool,
+    events: Vec<String>,
+    // seeds the scrambled durations, so each instance flickers its own fixed way
+    phase: f32,
+    // the frames entered so far
+    steps: u32,
 }
 
 impl Animation {
-    pub fn new(frames: Vec<Tile>) -> Animation {
-        Animation {
-            current: frames[0].clone(),
-            timer: Instant::now(),
-            frames,
+    pub fn new(clip: Clip, phase: f32) -> Animation {
+        let mut animation = Animation {
+            clip,
+            frame: 0,
+            timer: 0.0,
+            duration: 0.0,
+            started: false,
+            reversing: false,
+            finished: false,
+            events: Vec::new(),
+            phase,
+            steps: 0,
+        };
+        animation.enter_frame(0);
+        animation.events.clear();
+        animation
+    }
+
+    // giving the clip that is already playing carries on where it was
+    pub fn give_clip(&mut self, clip: &Clip) {
+        if &self.clip != clip {
+            *self = Animation::new(clip.clone(), self.phase);
         }
     }
 
-    pub fn give_frames(&mut self, frames: Vec<Tile>) {
-        self.frames = frames;
+    pub fn update(&mut self, delta: f32) {
+        self.events.clear();
+        if !self.started {
+            self.started = true;
+            self.enter_frame(self.frame);
+        }
+
+        self.timer += delta;
+        // frames without a duration are held until the clip changes,
+        // the time left over from a frame counts towards the next one
+        while !self.finished && self.duration > 0.0 && self.timer >= self.duration {
+            self.timer -= self.duration;
+            match self.get_next_frame() {
+                Some(frame) => self.enter_frame(frame),
+                None => self.finished = true,
+            }
+        }
     }
 
-    pub fn update(&mut self) {
-        if let Some(mut i) = self.frames.iter().position(|a| a == &self.current) {
-            if let Some(mut delay) = self.current.properties.delay {
-                if let Some(scramble_delay) = self.current.properties.scramble_delay {
-                    if scramble_delay {
-                        delay = rand::thread_rng().gen_range(delay as f32 * 0.6, delay as f32 * 1.4)
-                            as usize;
-                    }
+    fn get_next_frame(&mut self) -> Option<usize> {
+        let last = self.clip.frames.len() - 1;
+        match self.clip.mode {
+            Mode::Loop => Some(if self.frame == last {
+                0
+            } else {
+                self.frame + 1
+            }),
+            Mode::Once => {
+                if self.frame == last {
+                    None
+                } else {
+                    Some(self.frame + 1)
+                }
+            }
+            Mode::PingPong => {
+                if last == 0 {
+                    return Some(0);
                 }
-                if self.timer.elapsed().as_millis() > delay as u128 {
-                    i = if i == self.frames.len() - 1 { 0 } else { i + 1 };
-                    self.current = self.frames[i].clone();
-                    self.timer = Instant::now();
+                if self.frame == last {
+                    self.reversing = true;
+                } else if self.frame == 0 {
+                    self.reversing = false;
                 }
+                Some(if self.reversing {
+                    self.frame - 1
+                } else {
+                    self.frame + 1
+                })
             }
+        }
+    }
+
+    fn enter_frame(&mut self, frame: usize) {
+        self.frame = frame;
+        let current = &self.clip.frames[frame];
+
+        self.duration = if self.clip.scramble {
+            let hash = ((self.phase * 1000.0) as u32)
+                .wrapping_mul(73_856_093)
+                .wrapping_add(self.steps.wrapping_mul(19_349_663));
+            current.duration * (0.6 + 0.8 * (hash % 1000) as f32 / 1000.0)
         } else {
-            self.current = self.frames[0].clone();
+            current.duration
+        };
+        self.steps = self.steps.wrapping_add(1);
+        if let Some(event) = &current.event {
+            self.events.push(event.clone());
         }
     }
 
+    // one-shot clips finish after their last frame, and keep showing it
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // the events of the frames reached during the last update
+    pub fn get_events(&self) -> &[String] {
+        &self.events
+    }
+
+    pub fn get_frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn get_tile(&self) -> &Tile {
+        &self.clip.frames[self.frame].tile
+    }
+
     pub fn draw(&self, spritebatch: &mut SpriteBatch, position: Point2<f32>) {
-        self.current.draw(spritebatch, position);
+        self.get_tile().draw(spritebatch, position);
     }
 }
 
+const MOVING: [Action; 8] = [
+    Action::MovingUp,
+    Action::MovingDown,
+    Action::MovingLeft,
+    Action::MovingRight,
+    Action::MovingUpLeft,
+    Action::MovingUpRight,
+    Action::MovingDownLeft,
+    Action::MovingDownRight,
+];
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Animations {
-    available: HashMap<Action, Animation>,
+    available: HashMap<Action, Clip>,
     current: Animation,
 }
 
 impl Animations {
-    pub fn new(tileset: &Tileset) -> Animations {
-        let mut available = HashMap::new();
-
-        let mut idle = tileset.get_tile_by_entity_keyframe("player-top", 0);
-        idle.source.h *= 2.0;
-
-        let animation = Animation::new(vec![idle.clone()]);
-        available.insert(Action::IdleLeft, animation);
+    // clips are looked up per direction, e.g. "player-up" and "player-up-idle",
+    // falling back on other directions
+    pub fn new(tileset: &Tileset, entity: &str) -> Result<Animations, Error> {
+        let load = |name: &str| Animations::load_clip(tileset, &format!("{}-{}", entity, name));
+        let mirror = |clip: Clip| clip.map_tiles(flip);
 
-        let mut moving = tileset.get_tile_by_entity_keyframe("player-top", 1);
-        moving.source.h *= 2.0;
+        let (left, right) = match (load("left")?, load("right")?) {
+            (Some(left), Some(right)) => (left, right),
+            (Some(left), None) => (left.clone(), mirror(left)),
+            (None, Some(right)) => (mirror(right.clone()), right),
+            (None, None) => return Err(Error::MissingEntity(entity.to_string())),
+        };
+        let down = load("down")?.unwrap_or_else(|| left.clone());
+        let up = load("up")?.unwrap_or_else(|| down.clone());
 
-        let animation = Animation::new(vec![idle.clone(), moving.clone()]);
-        available.insert(Action::MovingLeft, animation.clone());
-        available.insert(Action::MovingUpLeft, animation.clone());
-        available.insert(Action::MovingDownLeft, animation);
+        let mut walking = HashMap::new();
+        walking.insert(Direction::Up, (up, "up-idle"));
+        walking.insert(Direction::Down, (down, "down-idle"));
+        walking.insert(Direction::Left, (left, "left-idle"));
+        walking.insert(Direction::Right, (right, "right-idle"));
 
-        let idle = flip(idle);
-        let moving = flip(moving);
+        let mut available = HashMap::new();
+        for (direction, (clip, idle)) in walking.iter() {
+            let idle = load(idle)?.unwrap_or_else(|| Clip::still(clip.frames[0].tile.clone()));
+            available.insert(Action::Idle(*direction), idle);
+        }
+        for action in MOVING.iter() {
+            let (clip, _) = &walking[&action.get_direction()];
+            available.insert(action.clone(), clip.clone());
+        }
 
-        let animation = Animation::new(vec![idle.clone()]);
-        available.insert(Action::IdleRight, animation);
+        Ok(Animations {
+            current: Animation::new(available[&Action::Idle(Direction::Down)].clone(), 0.0),
+            available,
+        })
+    }
 
-        let animation = Animation::new(vec![idle, moving]);
-        available.insert(Action::MovingRight, animation.clone());
-        available.insert(Action::MovingUpRight, animation.clone());
-        available.insert(Action::MovingDownRight, animation.clone());
+    // only a missing entity counts as no clip, a broken one is an error
+    fn load_clip(tileset: &Tileset, name: &str) -> Result<Option<Clip>, Error> {
+        let clip = match tileset.get_clip(name) {
+            Ok(clip) => clip,
+            Err(Error::MissingEntity(missing)) if missing == name => return Ok(None),
+            Err(error) => return Err(error),
+        };
 
-        Animations {
-            available,
-            current: animation,
-        }
+        // sprites are two tiles tall, the clip's tiles are their top halves
+        Ok(Some(clip.map_tiles(|mut tile| {
+            tile.source.h *= 2.0;
+            tile
+        })))
     }
 
-    pub fn update(&mut self, action: &Action) {
-        if let Some(animation) = self.available.get(&action).cloned() {
-            self.current.give_frames(animation.frames);
+    pub fn update(&mut self, action: &Action, delta: f32) {
+        if let Some(clip) = self.available.get(action) {
+            self.current.give_clip(clip);
         }
-        self.current.update();
+        self.current.update(delta);
     }
 
     pub fn draw(&self, spritebatch: &mut SpriteBatch, position: Point2<f32>) {
         self.current.draw(spritebatch, position)
     }
+
+    pub fn is_finished(&self) -> bool {
+        self.current.is_finished()
+    }
+
+    pub fn get_events(&self) -> &[String] {
+        self.current.get_events()
+    }
+
+    pub fn get_tile(&self) -> &Tile {
+        self.current.get_tile()
+    }
 }
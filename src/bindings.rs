@@ -0,0 +1,182 @@
+use ggez::event::{Axis, Button, KeyCode};
+use ggez::{filesystem, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+use std::fs;
+use std::io::Read;
+
+use crate::constants;
+use crate::error::Error;
+use crate::resources::Resources;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    NextResponse,
+    PrevResponse,
+    ChooseResponse,
+    Save,
+    Load,
+    Quit,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    keys: HashMap<Command, Vec<KeyCode>>,
+    #[serde(default)]
+    buttons: HashMap<Command, Vec<Button>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    keys: HashMap<KeyCode, Command>,
+    buttons: HashMap<Button, Command>,
+}
+
+impl Bindings {
+    pub fn new(resources: &mut dyn Resources) -> Result<Bindings, Error> {
+        let path = "/bindings";
+        let mut bindings = Bindings::default();
+        bindings
+            .give_overrides(resources.open(path)?)
+            .map_err(|e| e.in_file(path))?;
+        Ok(bindings)
+    }
+
+    // applied after the defaults from the resources directory, so the player's file wins:
+    // the commands it lists lose their default keys and buttons, the others keep them
+    pub fn load_user_config(&mut self, context: &mut Context) -> Result<(), Error> {
+        let path = filesystem::user_config_dir(context).join(constants::BINDINGS_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let display_path = path.display().to_string();
+        let file = fs::File::open(&path).map_err(|e| Error::Io(display_path.clone(), e))?;
+        self.give_overrides(file)
+            .map_err(|e| e.in_file(&display_path))
+    }
+
+    // every listed command loses its previous keys or buttons, unlisted commands keep theirs
+    pub fn give_overrides<R: Read>(&mut self, reader: R) -> Result<(), Error> {
+        let bad_json = |e| Error::BadJson("key bindings".to_string(), e);
+        let value: Value = serde_json::from_reader(reader).map_err(bad_json)?;
+        // files written before gamepads were supported only map commands to keys
+        let Config { keys, buttons } =
+            if value.get("keys").is_some() || value.get("buttons").is_some() {
+                serde_json::from_value(value).map_err(bad_json)?
+            } else {
+                Config {
+                    keys: serde_json::from_value(value).map_err(bad_json)?,
+                    buttons: HashMap::new(),
+                }
+            };
+
+        self.keys.retain(|_, command| !keys.contains_key(command));
+        for (command, keycodes) in keys {
+            for keycode in keycodes {
+                self.give_key(keycode, command);
+            }
+        }
+
+        self.buttons
+            .retain(|_, command| !buttons.contains_key(command));
+        for (command, buttons) in buttons {
+            for button in buttons {
+                self.give_button(button, command);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn give_key(&mut self, keycode: KeyCode, command: Command) {
+        self.keys.insert(keycode, command);
+    }
+
+    pub fn give_button(&mut self, button: Button, command: Command) {
+        self.buttons.insert(button, command);
+    }
+
+    pub fn get_command(&self, keycode: KeyCode) -> Option<Command> {
+        self.keys.get(&keycode).cloned()
+    }
+
+    pub fn get_button_command(&self, button: Button) -> Option<Command> {
+        self.buttons.get(&button).cloned()
+    }
+
+    pub fn get_keys(&self, command: Command) -> Vec<KeyCode> {
+        self.keys
+            .iter()
+            .filter(|(_, bound)| **bound == command)
+            .map(|(keycode, _)| *keycode)
+            .collect()
+    }
+}
+
+// holds the movement commands of whichever of the 8 directions the left stick points in
+#[derive(Debug, Clone, Default)]
+pub struct Stick {
+    x: f32,
+    y: f32,
+    held: Vec<Command>,
+}
+
+impl Stick {
+    pub fn new() -> Stick {
+        Stick::default()
+    }
+
+    // returns the commands the move presses and releases
+    pub fn give_axis(&mut self, axis: Axis, value: f32) -> (Vec<Command>, Vec<Command>) {
+        match axis {
+            Axis::LeftStickX => self.x = value,
+            Axis::LeftStickY => self.y = value,
+            _ => return (Vec::new(), Vec::new()),
+        }
+
+        let held = self.get_commands();
+        let pressed = held
+            .iter()
+            .filter(|command| !self.held.contains(command))
+            .cloned()
+            .collect();
+        let released = self
+            .held
+            .iter()
+            .filter(|command| !held.contains(command))
+            .cloned()
+            .collect();
+        self.held = held;
+
+        (pressed, released)
+    }
+
+    pub fn get_commands(&self) -> Vec<Command> {
+        if self.x.hypot(self.y) < constants::STICK_DEADZONE {
+            return Vec::new();
+        }
+
+        // sticks point up for positive y, sectors count anticlockwise from the right
+        match (self.y.atan2(self.x) / FRAC_PI_4).round() as isize {
+            0 => vec![Command::MoveRight],
+            1 => vec![Command::MoveUp, Command::MoveRight],
+            2 => vec![Command::MoveUp],
+            3 => vec![Command::MoveUp, Command::MoveLeft],
+            4 | -4 => vec![Command::MoveLeft],
+            -3 => vec![Command::MoveDown, Command::MoveLeft],
+            -2 => vec![Command::MoveDown],
+            _ => vec![Command::MoveDown, Command::MoveRight],
+        }
+    }
+}
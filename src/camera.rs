@@ -1,4 +1,5 @@
 use ggez::conf::Conf;
+use ggez::graphics::Rect;
 use ggez::nalgebra::Point2;
 
 use crate::constants;
@@ -19,20 +20,34 @@ impl Camera {
         }
     }
 
+    pub fn give_map_dimensions(&mut self, map_dimensions: (f32, f32)) {
+        self.map_dimensions = map_dimensions;
+    }
+
     pub fn give_center(&mut self, center: Point2<f32>) {
         self.draw.x = ((self.window_dimensions.0 / 2.0) - center.x) - (constants::TILE_WIDTH);
         self.draw.y = ((self.window_dimensions.1 / 2.0) - center.y) - (constants::TILE_HEIGHT);
 
         if self.draw.x > 0.0 {
             self.draw.x = 0.0;
-        } else if self.draw.x - self.window_dimensions.0 < -1.0 * self.map_dimensions.0 {
-            self.draw.x = -1.0 * (self.map_dimensions.0 - self.window_dimensions.0);
+        } else if self.draw.x - self.window_dimensions.0 < -self.map_dimensions.0 {
+            self.draw.x = -(self.map_dimensions.0 - self.window_dimensions.0);
         }
 
         if self.draw.y > 0.0 {
             self.draw.y = 0.0;
-        } else if self.draw.y - self.window_dimensions.1 < -1.0 * self.map_dimensions.1 {
-            self.draw.y = -1.0 * (self.map_dimensions.1 - self.window_dimensions.1);
+        } else if self.draw.y - self.window_dimensions.1 < -self.map_dimensions.1 {
+            self.draw.y = -(self.map_dimensions.1 - self.window_dimensions.1);
         }
     }
+
+    // the part of the map that is on screen
+    pub fn get_view(&self) -> Rect {
+        Rect::new(
+            -self.draw.x,
+            -self.draw.y,
+            self.window_dimensions.0,
+            self.window_dimensions.1,
+        )
+    }
 }
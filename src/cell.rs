@@ -1,45 +1,35 @@
-use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::nalgebra::Point2;
 
-use crate::animations::Animation;
 use crate::constants;
-use crate::entity::Operable;
-use crate::tileset::Tileset;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cell {
     pub id: usize,
+    pub tile: (usize, usize),
     pub destination: Point2<f32>,
-    animation: Animation,
-}
-
-impl Operable for Cell {
-    fn update(&mut self) {
-        self.animation.update();
-    }
-
-    fn draw(&self, spritebatch: &mut SpriteBatch) {
-        self.animation.draw(spritebatch, self.destination);
-    }
+    // a fixed pseudo-random fraction, so scrambled tiles don't all flicker in step
+    pub phase: f32,
 }
 
 impl Cell {
-    pub fn new(text: &str, i: usize, tileset: &Tileset, dimensions: (usize, usize)) -> Cell {
-        let id = text.parse::<usize>().unwrap();
-
-        let x = i as f32 % dimensions.0 as f32;
-        let y = (i as f32 / dimensions.1 as f32).floor();
+    pub fn new(id: usize, tile: (usize, usize)) -> Cell {
         let offset = (constants::TILE_WIDTH / 2.0) * constants::TILE_SCALE;
 
         let destination = Point2::new(
-            (constants::TILE_WIDTH * constants::TILE_SCALE * x) + offset,
-            (constants::TILE_HEIGHT * constants::TILE_SCALE * y) + offset,
+            (constants::TILE_WIDTH * constants::TILE_SCALE * tile.0 as f32) + offset,
+            (constants::TILE_HEIGHT * constants::TILE_SCALE * tile.1 as f32) + offset,
         );
 
+        let hash = (tile.0 as u32)
+            .wrapping_mul(73_856_093)
+            .wrapping_add((tile.1 as u32).wrapping_mul(19_349_663));
+        let phase = (hash % 1000) as f32 / 1000.0;
+
         Cell {
             id,
-            animation: tileset.get_animation(id),
+            tile,
             destination,
+            phase,
         }
     }
 }
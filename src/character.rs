@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::resources::Resources;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Routine {
+    #[default]
+    Wander,
+    Stand,
+    Post(String),
+    Patrol(Vec<String>),
+    Schedule(Vec<Appointment>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Appointment {
+    pub hour: f32,
+    pub routine: Routine,
+}
+
+impl Routine {
+    // map properties may hold a bare name like "stand" as well as json
+    pub fn parse(value: &str) -> Result<Routine, Error> {
+        let value = value.trim();
+        let json = if value.starts_with('{') {
+            value.to_string()
+        } else {
+            format!("\"{}\"", value)
+        };
+
+        serde_json::from_str(&json).map_err(|e| Error::BadJson("routine".to_string(), e))
+    }
+
+    // the routine a schedule calls for at the given hour, carrying over from the previous day
+    pub fn at(&self, hour: f32) -> &Routine {
+        match self {
+            Routine::Schedule(appointments) => appointments
+                .iter()
+                .filter(|appointment| appointment.hour <= hour)
+                .max_by(|a, b| a.hour.total_cmp(&b.hour))
+                .or_else(|| appointments.iter().max_by(|a, b| a.hour.total_cmp(&b.hour)))
+                .map_or(&Routine::Stand, |appointment| appointment.routine.at(hour)),
+            routine => routine,
+        }
+    }
+
+    pub fn get_points(&self) -> Vec<&str> {
+        match self {
+            Routine::Wander | Routine::Stand => Vec::new(),
+            Routine::Post(point) => vec![point.as_str()],
+            Routine::Patrol(points) => points.iter().map(String::as_str).collect(),
+            Routine::Schedule(appointments) => appointments
+                .iter()
+                .flat_map(|appointment| appointment.routine.get_points())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Character {
+    #[serde(skip)]
+    pub name: String,
+    pub entity: Option<String>,
+    pub speed: f32,
+    #[serde(default)]
+    pub wander_radius: f32,
+    pub dialogtree: Option<String>,
+    #[serde(default)]
+    pub routine: Routine,
+}
+
+impl Character {
+    pub fn load_all(resources: &mut dyn Resources) -> Result<HashMap<String, Character>, Error> {
+        let path = "/characters";
+        let mut characters: HashMap<String, Character> =
+            serde_json::from_reader(resources.open(path)?)
+                .map_err(|e| Error::BadJson(path.to_string(), e))?;
+
+        for (name, character) in characters.iter_mut() {
+            character.name = name.clone();
+        }
+
+        Ok(characters)
+    }
+
+    pub fn get<'a>(
+        characters: &'a HashMap<String, Character>,
+        name: &str,
+    ) -> Result<&'a Character, Error> {
+        characters
+            .get(name)
+            .ok_or_else(|| Error::UnknownCharacter(name.to_string()))
+    }
+
+    pub fn get_entity(&self) -> &str {
+        self.entity.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn get_dialogtree(&self) -> &str {
+        self.dialogtree.as_deref().unwrap_or(&self.name)
+    }
+}
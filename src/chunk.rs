@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+use crate::cell::Cell;
+use crate::constants;
+
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub position: (usize, usize),
+    pub dimensions: (usize, usize),
+    // kept for every chunk, collision and navigation are built from them,
+    // so streaming a chunk out only drops the cells derived from its ids
+    ids: Vec<usize>,
+    // the non-empty cells, only built while the chunk is near the camera
+    cells: Option<Vec<Cell>>,
+}
+
+impl Chunk {
+    // chunks start out non-resident, the first view given decides which are built
+    pub fn new(position: (usize, usize), dimensions: (usize, usize), ids: Vec<usize>) -> Chunk {
+        Chunk {
+            position,
+            dimensions,
+            ids,
+            cells: None,
+        }
+    }
+
+    // cuts a block of tiles into chunks of at most CHUNK_SIZE by CHUNK_SIZE
+    pub fn split(
+        position: (usize, usize),
+        dimensions: (usize, usize),
+        ids: &[usize],
+    ) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+
+        for top in (0..dimensions.1).step_by(constants::CHUNK_SIZE) {
+            for left in (0..dimensions.0).step_by(constants::CHUNK_SIZE) {
+                let width = constants::CHUNK_SIZE.min(dimensions.0 - left);
+                let height = constants::CHUNK_SIZE.min(dimensions.1 - top);
+                let chunk_ids = (top..top + height)
+                    .flat_map(|y| {
+                        (left..left + width)
+                            .map(move |x| ids.get(x + y * dimensions.0).cloned().unwrap_or(0))
+                    })
+                    .collect();
+
+                chunks.push(Chunk::new(
+                    (position.0 + left, position.1 + top),
+                    (width, height),
+                    chunk_ids,
+                ));
+            }
+        }
+
+        chunks
+    }
+
+    pub fn give_resident(&mut self, resident: bool) {
+        if !resident {
+            self.cells = None;
+        } else if self.cells.is_none() {
+            self.cells = Some(self.get_cells().filter(|cell| cell.id != 0).collect());
+        }
+    }
+
+    pub fn is_resident(&self) -> bool {
+        self.cells.is_some()
+    }
+
+    pub fn get_ids(&self) -> &[usize] {
+        &self.ids
+    }
+
+    // every cell of the chunk, whether it is resident or not
+    pub fn get_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.ids.iter().enumerate().map(move |(i, id)| {
+            Cell::new(
+                *id,
+                (
+                    self.position.0 + i % self.dimensions.0,
+                    self.position.1 + i / self.dimensions.0,
+                ),
+            )
+        })
+    }
+
+    pub fn get_resident_cells(&self) -> &[Cell] {
+        self.cells.as_deref().unwrap_or_default()
+    }
+
+    pub fn overlaps(&self, columns: &Range<usize>, rows: &Range<usize>) -> bool {
+        self.position.0 < columns.end
+            && columns.start < self.position.0 + self.dimensions.0
+            && self.position.1 < rows.end
+            && rows.start < self.position.1 + self.dimensions.1
+    }
+}
@@ -0,0 +1,82 @@
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use crate::constants;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clock {
+    // the seconds into the current day, kept below DAY_LENGTH so steps stay precise
+    time: f32,
+    #[serde(default)]
+    day: usize,
+}
+
+impl Clock {
+    pub fn new(hour: f32) -> Clock {
+        Clock {
+            time: hour / 24.0 * constants::DAY_LENGTH,
+            day: 0,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        // saves from before days were counted may hold many days in time
+        while self.time >= constants::DAY_LENGTH {
+            self.time -= constants::DAY_LENGTH;
+            self.day += 1;
+        }
+    }
+
+    pub fn get_day(&self) -> usize {
+        self.day + (self.time / constants::DAY_LENGTH) as usize
+    }
+
+    pub fn give_day(&mut self, day: usize) {
+        self.day = day;
+        self.time %= constants::DAY_LENGTH;
+    }
+
+    pub fn get_hour(&self) -> f32 {
+        (self.time % constants::DAY_LENGTH) / constants::DAY_LENGTH * 24.0
+    }
+
+    pub fn give_hour(&mut self, hour: f32) {
+        self.day = self.get_day();
+        self.time = hour / 24.0 * constants::DAY_LENGTH;
+    }
+
+    // ranges may wrap past midnight, e.g. from 20 to 6
+    pub fn is_between(&self, from: f32, to: f32) -> bool {
+        let hour = self.get_hour();
+        if from <= to {
+            hour >= from && hour < to
+        } else {
+            hour >= from || hour < to
+        }
+    }
+
+    // full daylight for most of the day, fading into the night tint around dawn and dusk
+    pub fn get_tint(&self) -> Color {
+        let sun = ((self.get_hour() - constants::DAWN_HOUR)
+            / (constants::DUSK_HOUR - constants::DAWN_HOUR)
+            * PI)
+            .sin();
+        let daylight = (sun * 2.0).clamp(0.0, 1.0);
+
+        let night = constants::NIGHT_TINT;
+        Color::new(
+            night.r + (1.0 - night.r) * daylight,
+            night.g + (1.0 - night.g) * daylight,
+            night.b + (1.0 - night.b) * daylight,
+            1.0,
+        )
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new(constants::START_HOUR)
+    }
+}
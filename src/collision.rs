@@ -0,0 +1,117 @@
+use ggez::nalgebra::Point2;
+use std::collections::HashMap;
+
+use crate::constants;
+use crate::layer::Layer;
+use crate::tileset::Tileset;
+
+#[derive(Debug, Clone, Default)]
+pub struct Collision {
+    // keyed by chunk, so sparse infinite maps only hold the chunks with solid tiles
+    solid: HashMap<(usize, usize), Vec<bool>>,
+    dimensions: (usize, usize),
+}
+
+impl Collision {
+    pub fn new(
+        layers: &[Layer],
+        collision_layers: &[Layer],
+        tileset: &Tileset,
+        dimensions: (usize, usize),
+    ) -> Collision {
+        let mut solid = HashMap::new();
+        let mut give_solid = |(x, y): (usize, usize)| {
+            if x < dimensions.0 && y < dimensions.1 {
+                let (chunk, index) = Collision::get_chunk((x, y));
+                solid.entry(chunk).or_insert_with(|| {
+                    vec![false; constants::CHUNK_SIZE * constants::CHUNK_SIZE]
+                })[index] = true;
+            }
+        };
+
+        for layer in layers.iter() {
+            for cell in layer.get_cells() {
+                if tileset.is_solid(cell.id) {
+                    give_solid(cell.tile);
+                }
+            }
+        }
+
+        for layer in collision_layers.iter() {
+            for cell in layer.get_cells() {
+                if cell.id != 0 {
+                    give_solid(cell.tile);
+                }
+            }
+        }
+
+        Collision { solid, dimensions }
+    }
+
+    pub fn is_solid(&self, x: usize, y: usize) -> bool {
+        let (chunk, index) = Collision::get_chunk((x, y));
+        self.solid.get(&chunk).is_some_and(|solid| solid[index])
+    }
+
+    // the chunk holding a tile and the tile's index inside it
+    pub fn get_chunk(tile: (usize, usize)) -> ((usize, usize), usize) {
+        (
+            (
+                tile.0 / constants::CHUNK_SIZE,
+                tile.1 / constants::CHUNK_SIZE,
+            ),
+            tile.0 % constants::CHUNK_SIZE
+                + (tile.1 % constants::CHUNK_SIZE) * constants::CHUNK_SIZE,
+        )
+    }
+
+    pub fn get_solid_chunks(&self) -> impl Iterator<Item = &(usize, usize)> {
+        self.solid.keys()
+    }
+
+    pub fn collides(&self, position: Point2<f32>) -> bool {
+        self.get_overlap(position) > 0.0
+    }
+
+    // the area of an entity's hitbox that lies inside solid tiles
+    pub fn get_overlap(&self, position: Point2<f32>) -> f32 {
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+
+        // only the feet of an entity collide, so it can stand in front of walls
+        let left = position.x - (pixel_width * constants::HITBOX_WIDTH / 2.0);
+        let right = position.x + (pixel_width * constants::HITBOX_WIDTH / 2.0);
+        let top = position.y + pixel_height * (1.0 - constants::HITBOX_HEIGHT);
+        let bottom = position.y + pixel_height;
+
+        let first_column = (left.max(0.0) / pixel_width) as usize;
+        let last_column = ((right - constants::FLOAT_PRECISION).max(0.0) / pixel_width) as usize;
+        let first_row = (top.max(0.0) / pixel_height) as usize;
+        let last_row = ((bottom - constants::FLOAT_PRECISION).max(0.0) / pixel_height) as usize;
+
+        let mut overlap = 0.0;
+        for y in first_row..=last_row {
+            for x in first_column..=last_column {
+                if self.is_solid(x, y) {
+                    let tile_left = x as f32 * pixel_width;
+                    let tile_top = y as f32 * pixel_height;
+                    let width = right.min(tile_left + pixel_width) - left.max(tile_left);
+                    let height = bottom.min(tile_top + pixel_height) - top.max(tile_top);
+                    overlap += width.max(0.0) * height.max(0.0);
+                }
+            }
+        }
+        overlap
+    }
+
+    pub fn get_tile_dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    pub fn get_dimensions(&self) -> (f32, f32) {
+        (
+            (constants::TILE_WIDTH * constants::TILE_SCALE) * self.dimensions.0 as f32,
+            (constants::TILE_HEIGHT * constants::TILE_SCALE) * self.dimensions.1 as f32,
+        )
+    }
+}
@@ -2,10 +2,30 @@ pub const TILE_WIDTH: f32 = 16.0;
 pub const TILE_HEIGHT: f32 = 16.0;
 pub const TILE_SCALE: f32 = 2.75;
 
-pub const PLAYER_SPEED: f32 = 3.0;
-pub const WANDER_DISTANCE: f32 = 200.0;
+pub const UPDATES_PER_SECOND: u32 = 60;
+pub const DAY_LENGTH: f32 = 1200.0;
+pub const START_HOUR: f32 = 8.0;
+pub const DAWN_HOUR: f32 = 5.0;
+pub const DUSK_HOUR: f32 = 21.0;
+
 pub const INTERACT_DISTANCE: f32 = 30.0;
-pub const WAIT_TIME: u64 = 3;
+pub const WAIT_TIME: f32 = 3.0;
+pub const MESSAGE_TIME: f32 = 4.0;
+pub const WAYPOINT_DISTANCE: f32 = 4.0;
+pub const STICK_DEADZONE: f32 = 0.3;
+pub const WANDER_ATTEMPTS: usize = 10;
+pub const PATH_NODES: usize = 1024;
+pub const VIEW_MARGIN: usize = 2;
+pub const CHUNK_SIZE: usize = 16;
+pub const RESIDENT_CHUNKS: usize = 1;
+
+pub const HITBOX_WIDTH: f32 = 0.5;
+pub const HITBOX_HEIGHT: f32 = 0.5;
+pub const COLLISION_LAYER: &str = "Collision";
+pub const START_MAP: &str = "forum";
+pub const PLAYER: &str = "player";
+pub const SAVE_FILE: &str = "save.json";
+pub const BINDINGS_FILE: &str = "bindings.json";
 
 pub const FLOAT_PRECISION: f32 = 0.001;
 
@@ -18,3 +38,4 @@ use ggez::graphics::Color;
 pub const PURPLE: Color = Color::new(0.4, 0.0, 0.2, 1.0);
 pub const GOLD: Color = Color::new(1.0, 0.872, 0.376, 1.0);
 pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+pub const NIGHT_TINT: Color = Color::new(0.25, 0.3, 0.55, 1.0);
@@ -3,51 +3,217 @@ use ggez::graphics::{
     self, DrawMode, DrawParam, Font, Mesh, MeshBuilder, Rect, Scale, Text, TextFragment,
 };
 use ggez::nalgebra::Point2;
-use ggez::{filesystem, Context, GameResult};
+use ggez::{Context, GameResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Read;
 
 use crate::constants;
-use crate::npc::Character;
+use crate::error::Error;
+use crate::gamestate::{Condition, Effect, GameState};
+use crate::resources::Resources;
+use crate::save::DialogState;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    text: String,
+    dialog: Option<usize>,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    #[serde(default)]
+    effects: Vec<Effect>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Dialog {
     text: String,
-    responses: Vec<(usize, String)>,
+    responses: Vec<Response>,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    #[serde(default)]
+    effects: Vec<Effect>,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct DialogTree {
     dialogs: HashMap<usize, Dialog>,
+    speaker: String,
 }
 
 impl DialogTree {
-    pub fn new(context: &mut Context, character: Character) -> DialogTree {
-        DialogTree {
-            dialogs: serde_json::from_reader(
-                filesystem::open(context, "/dialogtrees/".to_string() + character.to_str())
-                    .unwrap(),
-            )
-            .unwrap(),
+    pub fn new(resources: &mut dyn Resources, name: &str) -> Result<DialogTree, Error> {
+        let path = "/dialogtrees/".to_string() + name;
+        DialogTree::from_reader(resources.open(&path)?).map_err(|e| e.in_file(&path))
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<DialogTree, Error> {
+        Ok(DialogTree {
+            dialogs: serde_json::from_reader(reader)
+                .map_err(|e| Error::BadJson("dialog tree".to_string(), e))?,
+            speaker: String::new(),
+        })
+    }
+
+    pub fn give_speaker(&mut self, speaker: &str) {
+        self.speaker = speaker.to_string();
+    }
+
+    // a response is only offered when both it and the dialog it leads to are allowed
+    fn is_available(&self, response: &Response, gamestate: &GameState) -> bool {
+        let dialog_allowed = match response.dialog.and_then(|id| self.dialogs.get(&id)) {
+            Some(dialog) => gamestate.check_all(&dialog.conditions),
+            None => true,
+        };
+
+        gamestate.check_all(&response.conditions) && dialog_allowed
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Conversation {
+    dialog: usize,
+    dialogtree: DialogTree,
+    responses: Vec<Response>,
+    selected_response: usize,
+}
+
+impl Conversation {
+    pub fn new(dialogtree: DialogTree, gamestate: &mut GameState) -> Option<Conversation> {
+        let conversation = Conversation::load(
+            dialogtree,
+            &DialogState {
+                dialog: 0,
+                response: 0,
+            },
+            gamestate,
+        )?;
+
+        if gamestate.apply_all(&conversation.get_dialog().effects) {
+            Some(conversation)
+        } else {
+            None
+        }
+    }
+
+    pub fn load(
+        dialogtree: DialogTree,
+        state: &DialogState,
+        gamestate: &GameState,
+    ) -> Option<Conversation> {
+        let dialog = dialogtree.dialogs.get(&state.dialog)?;
+        if !gamestate.check_all(&dialog.conditions) {
+            return None;
+        }
+
+        let responses = dialog
+            .responses
+            .iter()
+            .filter(|response| dialogtree.is_available(response, gamestate))
+            .cloned()
+            .collect();
+
+        Some(Conversation {
+            dialog: state.dialog,
+            dialogtree,
+            responses,
+            selected_response: state.response,
+        })
+    }
+
+    pub fn save(&self) -> DialogState {
+        DialogState {
+            dialog: self.dialog,
+            response: self.selected_response,
+        }
+    }
+
+    fn get_dialog(&self) -> &Dialog {
+        &self.dialogtree.dialogs[&self.dialog]
+    }
+
+    pub fn get_speaker(&self) -> &str {
+        &self.dialogtree.speaker
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.get_dialog().text
+    }
+
+    pub fn get_responses(&self) -> Vec<&str> {
+        self.responses
+            .iter()
+            .map(|response| response.text.as_str())
+            .collect()
+    }
+
+    pub fn get_selected_response(&self) -> usize {
+        self.selected_response
+    }
+
+    // returns None once the chosen response ends the conversation
+    pub fn choose_reponse(self, gamestate: &mut GameState) -> Option<Conversation> {
+        let response = match self.responses.get(self.selected_response) {
+            Some(response) => response,
+            None => return Some(self),
+        };
+
+        if !gamestate.apply_all(&response.effects) {
+            return None;
+        }
+
+        let conversation = Conversation::load(
+            self.dialogtree.clone(),
+            &DialogState {
+                dialog: response.dialog?,
+                response: 0,
+            },
+            gamestate,
+        )?;
+
+        if gamestate.apply_all(&conversation.get_dialog().effects) {
+            Some(conversation)
+        } else {
+            None
         }
     }
+
+    pub fn next_response(&mut self) {
+        self.selected_response =
+            if Some(self.selected_response) < self.responses.len().checked_sub(1) {
+                self.selected_response + 1
+            } else {
+                0
+            };
+    }
+
+    pub fn prev_response(&mut self) {
+        self.selected_response = if self.selected_response == 0 {
+            self.responses.len().saturating_sub(1)
+        } else {
+            self.selected_response - 1
+        };
+    }
 }
 
 #[derive(Clone)]
 pub struct DialogBox {
-    display: Option<(Dialog, DialogTree, usize)>,
+    conversation: Option<Conversation>,
     font: Font,
     mesh: Mesh,
     conf: Conf,
+    // a notice such as a failed save, with the seconds it stays on screen
+    message: Option<(String, f32)>,
 }
 
 impl DialogBox {
-    pub fn new(context: &mut Context) -> DialogBox {
+    pub fn new(context: &mut Context) -> Result<DialogBox, Error> {
         let conf = Conf::new();
+        let font_path = "/fonts/SONORM__.ttf";
 
-        DialogBox {
-            display: None,
-            font: Font::new(context, "/fonts/SONORM__.ttf").unwrap(),
+        Ok(DialogBox {
+            conversation: None,
+            font: Font::new(context, font_path)
+                .map_err(|e| Error::MissingResource(font_path.to_string(), e))?,
             mesh: MeshBuilder::new()
                 .rectangle(
                     DrawMode::fill(),
@@ -60,26 +226,77 @@ impl DialogBox {
                     constants::PURPLE,
                 )
                 .build(context)
-                .unwrap(),
+                .map_err(Error::Graphics)?,
             conf,
-        }
+            message: None,
+        })
     }
 
     pub fn is_visible(&self) -> bool {
-        self.display.is_some()
+        self.conversation.is_some()
+    }
+
+    pub fn give_message(&mut self, message: String) {
+        self.message = Some((message, constants::MESSAGE_TIME));
     }
 
-    pub fn update(&mut self) {}
+    pub fn update(&mut self, delta: f32) {
+        if let Some((_, time)) = &mut self.message {
+            *time -= delta;
+            if *time <= 0.0 {
+                self.message = None;
+            }
+        }
+    }
 
     pub fn draw(&self, context: &mut Context) -> GameResult {
-        if let Some((dialog, _, selected_response)) = &self.display {
+        if let Some((message, _)) = &self.message {
+            let text = Text::new(
+                TextFragment::new(message.as_str())
+                    .font(self.font)
+                    .scale(Scale::uniform(30.0)),
+            );
+
+            graphics::draw(
+                context,
+                &text,
+                DrawParam::default()
+                    .dest(Point2::new(
+                        self.conf.window_mode.width * 0.02,
+                        self.conf.window_mode.height * 0.02,
+                    ))
+                    .color(constants::GOLD),
+            )?;
+        }
+
+        if let Some(conversation) = &self.conversation {
             let text = Text::new(
-                TextFragment::new(dialog.text.as_str())
+                TextFragment::new(conversation.get_text())
                     .font(self.font)
                     .scale(Scale::uniform(40.0)),
             );
 
             graphics::draw(context, &self.mesh, DrawParam::default())?;
+
+            if !conversation.get_speaker().is_empty() {
+                let speaker = Text::new(
+                    TextFragment::new(conversation.get_speaker())
+                        .font(self.font)
+                        .scale(Scale::uniform(40.0)),
+                );
+
+                graphics::draw(
+                    context,
+                    &speaker,
+                    DrawParam::default()
+                        .dest(Point2::new(
+                            self.conf.window_mode.width * 0.11,
+                            2.3 * self.conf.window_mode.height / 4.0,
+                        ))
+                        .color(constants::GOLD),
+                )?;
+            }
+
             graphics::draw(
                 context,
                 &text,
@@ -89,15 +306,15 @@ impl DialogBox {
                 )),
             )?;
 
-            for (i, response) in dialog.responses.iter().enumerate() {
-                let color = if &i == selected_response {
+            for (i, response) in conversation.get_responses().into_iter().enumerate() {
+                let color = if i == conversation.get_selected_response() {
                     constants::GOLD
                 } else {
                     constants::WHITE
                 };
 
                 let text = Text::new(
-                    TextFragment::new(response.1.as_str())
+                    TextFragment::new(response)
                         .font(self.font)
                         .scale(Scale::uniform(40.0)),
                 );
@@ -118,50 +335,43 @@ impl DialogBox {
         Ok(())
     }
 
-    pub fn populate_display(&mut self, dialogtree: Option<DialogTree>) {
-        if let Some(dialogtree) = &dialogtree {
-            let dialog = dialogtree.dialogs.get(&0).unwrap();
-            self.display = Some((dialog.clone(), dialogtree.clone(), 0));
-        } else {
-            self.display = None;
-        }
+    pub fn populate_display(&mut self, dialogtree: Option<DialogTree>, gamestate: &mut GameState) {
+        self.conversation =
+            dialogtree.and_then(|dialogtree| Conversation::new(dialogtree, gamestate));
     }
 
-    pub fn choose_reponse(&mut self) {
-        if let Some((dialog, dialogtree, selected_response)) = &self.display.clone() {
-            if let Some(selected_dialog) = dialog.responses.get(*selected_response) {
-                if let Some(new_dialog) = dialogtree.dialogs.get(&selected_dialog.0) {
-                    self.display = Some((new_dialog.clone(), dialogtree.clone(), 0));
-                }
-            }
-        }
+    pub fn save(&self) -> Option<DialogState> {
+        self.conversation.as_ref().map(Conversation::save)
     }
 
-    pub fn next_response(&mut self) {
-        if let Some((dialog, dialogtree, selected_response)) = &self.display.clone() {
-            let new_selected_response =
-                if Some(*selected_response) < dialog.responses.len().checked_sub(1) {
-                    selected_response + 1
-                } else {
-                    0
-                };
+    pub fn load(
+        &mut self,
+        dialogtree: Option<DialogTree>,
+        state: Option<&DialogState>,
+        gamestate: &GameState,
+    ) {
+        self.conversation = match (dialogtree, state) {
+            (Some(dialogtree), Some(state)) => Conversation::load(dialogtree, state, gamestate),
+            _ => None,
+        };
+    }
 
-            self.display = Some((dialog.clone(), dialogtree.clone(), new_selected_response));
+    pub fn choose_reponse(&mut self, gamestate: &mut GameState) {
+        self.conversation = self
+            .conversation
+            .take()
+            .and_then(|conversation| conversation.choose_reponse(gamestate));
+    }
+
+    pub fn next_response(&mut self) {
+        if let Some(conversation) = &mut self.conversation {
+            conversation.next_response();
         }
     }
 
     pub fn prev_response(&mut self) {
-        if let Some((dialog, dialogtree, selected_response)) = &self.display.clone() {
-            let new_selected_response = if selected_response == &0 {
-                match dialog.responses.len().checked_sub(1) {
-                    Some(i) => i,
-                    None => 0,
-                }
-            } else {
-                selected_response - 1
-            };
-
-            self.display = Some((dialog.clone(), dialogtree.clone(), new_selected_response));
+        if let Some(conversation) = &mut self.conversation {
+            conversation.prev_response();
         }
     }
 }
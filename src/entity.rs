@@ -1,10 +1,14 @@
 use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
 
+use crate::collision::Collision;
 use crate::constants;
+use crate::save::EntityState;
 
 pub trait Operable {
-    fn update(&mut self);
+    fn update(&mut self, delta: f32);
     fn draw(&self, spritebatch: &mut SpriteBatch);
 }
 
@@ -13,69 +17,118 @@ pub struct Entity {
     pub position: Point2<f32>,
     pub spawn: Point2<f32>,
     pub action: Action,
-    map_dimensions: (f32, f32),
+    speed: f32,
+    collision: Rc<Collision>,
 }
 
 impl Entity {
-    pub fn new(spawn: Point2<f32>, map_dimensions: (f32, f32)) -> Entity {
+    pub fn new(spawn: Point2<f32>, speed: f32, collision: Rc<Collision>) -> Entity {
         Entity {
             spawn,
-            action: Action::IdleLeft,
+            action: Action::Idle(Direction::Down),
             position: spawn,
-            map_dimensions,
+            speed,
+            collision,
         }
     }
 
-    pub fn update(&mut self) {
-        self.move_position();
+    pub fn update(&mut self, delta: f32) {
+        self.move_position(delta);
     }
 
-    fn move_position(&mut self) {
-        match self.action {
-            Action::MovingUp => self.position.y -= constants::PLAYER_SPEED,
-            Action::MovingUpLeft => {
-                self.position.x -= constants::PLAYER_SPEED / 2.0_f32.sqrt();
-                self.position.y -= constants::PLAYER_SPEED / 2.0_f32.sqrt();
-            }
-            Action::MovingUpRight => {
-                self.position.x += constants::PLAYER_SPEED / 2.0_f32.sqrt();
-                self.position.y -= constants::PLAYER_SPEED / 2.0_f32.sqrt();
-            }
-            Action::MovingLeft => self.position.x -= constants::PLAYER_SPEED,
-            Action::MovingDown => self.position.y += constants::PLAYER_SPEED,
-            Action::MovingDownLeft => {
-                self.position.x -= constants::PLAYER_SPEED / 2.0_f32.sqrt();
-                self.position.y += constants::PLAYER_SPEED / 2.0_f32.sqrt();
-            }
-            Action::MovingDownRight => {
-                self.position.x += constants::PLAYER_SPEED / 2.0_f32.sqrt();
-                self.position.y += constants::PLAYER_SPEED / 2.0_f32.sqrt();
+    pub fn teleport(&mut self, spawn: Point2<f32>, collision: Rc<Collision>) {
+        self.spawn = spawn;
+        self.position = spawn;
+        self.collision = collision;
+    }
+
+    pub fn save(&self) -> EntityState {
+        EntityState {
+            position: (self.position.x, self.position.y),
+            action: self.action.clone(),
+        }
+    }
+
+    pub fn load(&mut self, state: &EntityState) {
+        self.position = Point2::new(state.position.0, state.position.1);
+        self.action = state.action.clone();
+    }
+
+    pub fn get_feet(&self) -> Point2<f32> {
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+        Point2::new(
+            self.position.x,
+            self.position.y + pixel_height * (1.0 - constants::HITBOX_HEIGHT / 2.0),
+        )
+    }
+
+    fn move_position(&mut self, delta: f32) {
+        let speed = self.speed * delta;
+        let diagonal = speed / 2.0_f32.sqrt();
+        let (dx, dy) = match self.action {
+            Action::MovingUp => (0.0, -speed),
+            Action::MovingUpLeft => (-diagonal, -diagonal),
+            Action::MovingUpRight => (diagonal, -diagonal),
+            Action::MovingLeft => (-speed, 0.0),
+            Action::MovingDown => (0.0, speed),
+            Action::MovingDownLeft => (-diagonal, diagonal),
+            Action::MovingDownRight => (diagonal, diagonal),
+            Action::MovingRight => (speed, 0.0),
+            Action::Idle(_) => (0.0, 0.0),
+        };
+
+        let x = self.position.x;
+        let y = self.position.y;
+
+        // when a diagonal step is blocked, try each axis alone to slide along the wall
+        for destination in [
+            Point2::new(x + dx, y + dy),
+            Point2::new(x + dx, y),
+            Point2::new(x, y + dy),
+        ]
+        .iter()
+        {
+            if self.can_move_to(*destination) {
+                self.position = *destination;
+                break;
             }
-            Action::MovingRight => self.position.x += constants::PLAYER_SPEED,
-            Action::IdleLeft | Action::IdleRight => (),
         }
 
         let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
         let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+        let map_dimensions = self.collision.get_dimensions();
 
         if self.position.x < 0.0 {
             self.position.x = 0.0;
-        } else if self.position.x + pixel_height > self.map_dimensions.0 {
-            self.position.x = self.map_dimensions.0 - pixel_width;
+        } else if self.position.x + pixel_height > map_dimensions.0 {
+            self.position.x = map_dimensions.0 - pixel_width;
         }
 
         if self.position.y < 0.0 {
             self.position.y = 0.0;
-        } else if self.position.y + pixel_height > self.map_dimensions.1 {
-            self.position.y = self.map_dimensions.1 - pixel_height;
+        } else if self.position.y + pixel_height > map_dimensions.1 {
+            self.position.y = map_dimensions.1 - pixel_height;
         }
     }
+
+    fn can_move_to(&self, destination: Point2<f32>) -> bool {
+        // an entity already overlapping something solid may only walk out of it
+        let overlap = self.collision.get_overlap(destination);
+        overlap <= 0.0 || overlap < self.collision.get_overlap(self.position)
+    }
 }
 
-#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Action {
-    IdleLeft,
-    IdleRight,
+    Idle(Direction),
     MovingUp,
     MovingDown,
     MovingLeft,
@@ -85,3 +138,22 @@ pub enum Action {
     MovingDownLeft,
     MovingDownRight,
 }
+
+impl Action {
+    // diagonals face sideways, the way their sprites are drawn
+    pub fn get_direction(&self) -> Direction {
+        match self {
+            Action::Idle(direction) => *direction,
+            Action::MovingUp => Direction::Up,
+            Action::MovingDown => Direction::Down,
+            Action::MovingLeft | Action::MovingUpLeft | Action::MovingDownLeft => Direction::Left,
+            Action::MovingRight | Action::MovingUpRight | Action::MovingDownRight => {
+                Direction::Right
+            }
+        }
+    }
+
+    pub fn get_idle(&self) -> Action {
+        Action::Idle(self.get_direction())
+    }
+}
@@ -0,0 +1,75 @@
+use ggez::GameError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Xml(xml::reader::Error),
+    MissingElement(String),
+    MissingAttribute(String, String),
+    BadValue(String, String),
+    BadLayerData(String),
+    MissingTile(usize),
+    MissingEntity(String),
+    MissingSpawn(String),
+    MissingPoint(String),
+    UnknownMap(String),
+    UnknownCharacter(String),
+    MissingSaveState(String),
+    UnknownSaveState(String),
+    MissingResource(String, GameError),
+    BadJson(String, serde_json::Error),
+    Graphics(GameError),
+    Io(String, std::io::Error),
+    InFile(String, Box<Error>),
+}
+
+impl Error {
+    pub fn in_file(self, path: &str) -> Error {
+        Error::InFile(path.to_string(), Box::new(self))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Xml(error) => write!(f, "xml error at {}", error),
+            Error::MissingElement(element) => write!(f, "missing <{}> element", element),
+            Error::MissingAttribute(element, attribute) => {
+                write!(
+                    f,
+                    "<{}> is missing the \"{}\" attribute",
+                    element, attribute
+                )
+            }
+            Error::BadValue(name, value) => write!(f, "bad value \"{}\" for {}", value, name),
+            Error::BadLayerData(reason) => write!(f, "bad layer data: {}", reason),
+            Error::MissingTile(id) => write!(f, "no tile with id {} in the tileset", id),
+            Error::MissingEntity(entity) => write!(f, "no \"{}\" entity in the tileset", entity),
+            Error::MissingSpawn(character) => write!(f, "no spawn point for {}", character),
+            Error::MissingPoint(name) => write!(f, "no point named {} in the map", name),
+            Error::UnknownMap(name) => write!(f, "no map named {} in the registry", name),
+            Error::UnknownCharacter(name) => write!(f, "no character named {}", name),
+            Error::MissingSaveState(id) => write!(f, "the save has no state for npc {}", id),
+            Error::UnknownSaveState(id) => write!(f, "the save has a state for unknown npc {}", id),
+            Error::MissingResource(path, error) => write!(f, "can't open {}: {}", path, error),
+            Error::BadJson(path, error) => write!(f, "bad json in {}: {}", path, error),
+            Error::Graphics(error) => write!(f, "graphics error: {}", error),
+            Error::Io(path, error) => write!(f, "can't access {}: {}", path, error),
+            Error::InFile(path, error) => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<xml::reader::Error> for Error {
+    fn from(error: xml::reader::Error) -> Error {
+        Error::Xml(error)
+    }
+}
+
+impl From<Error> for GameError {
+    fn from(error: Error) -> GameError {
+        GameError::ResourceLoadError(error.to_string())
+    }
+}
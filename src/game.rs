@@ -1,8 +1,10 @@
-use ggez::event::{EventHandler, KeyCode, KeyMods};
+use ggez::event::{Axis, Button, EventHandler, GamepadId, KeyCode, KeyMods};
 use ggez::graphics::{self, spritebatch::SpriteBatch, DrawParam, FilterMode, Image, WrapMode};
-use ggez::{Context, GameResult};
+use ggez::{timer, Context, GameResult};
 
+use crate::bindings::{Bindings, Command, Stick};
 use crate::camera::Camera;
+use crate::constants;
 use crate::dialogbox::DialogBox;
 use crate::entity::Operable;
 use crate::world::World;
@@ -12,6 +14,8 @@ pub struct Game {
     spritebatch: SpriteBatch,
     dialogbox: DialogBox,
     camera: Camera,
+    bindings: Bindings,
+    stick: Stick,
 }
 
 impl Game {
@@ -19,28 +23,77 @@ impl Game {
         let mut image = Image::new(context, "/tileset.png")?;
         image.set_filter(FilterMode::Nearest);
         image.set_wrap(WrapMode::Mirror, WrapMode::Mirror);
-        let world = World::new(context);
+        let world = World::new(context)?;
         let dimensions = world.get_dimensions();
+        let mut bindings = Bindings::new(context)?;
+        bindings.load_user_config(context)?;
 
         Ok(Game {
             world,
             spritebatch: SpriteBatch::new(image),
-            dialogbox: DialogBox::new(context),
+            dialogbox: DialogBox::new(context)?,
             camera: Camera::new(dimensions),
+            bindings,
+            stick: Stick::new(),
         })
     }
+
+    fn give_command_down(&mut self, context: &mut Context, command: Command) {
+        match command {
+            Command::Quit => context.continuing = false,
+            Command::Interact => {
+                let dialogtree = self.world.get_dialogtree();
+                self.dialogbox
+                    .populate_display(dialogtree, &mut self.world.gamestate);
+                if !self.dialogbox.is_visible() {
+                    self.world.stop_talking();
+                }
+            }
+            Command::NextResponse => self.dialogbox.next_response(),
+            Command::PrevResponse => self.dialogbox.prev_response(),
+            Command::ChooseResponse => {
+                self.dialogbox.choose_reponse(&mut self.world.gamestate);
+                if !self.dialogbox.is_visible() {
+                    self.world.stop_talking();
+                }
+            }
+            Command::Save => {
+                if let Err(error) = self.world.save(context, &self.dialogbox) {
+                    self.dialogbox.give_message(error.to_string());
+                }
+            }
+            Command::Load => match self.world.load(context, &mut self.dialogbox) {
+                Ok(()) => self.camera.give_map_dimensions(self.world.get_dimensions()),
+                Err(error) => self.dialogbox.give_message(error.to_string()),
+            },
+            _ => self.world.give_command_down(command),
+        }
+    }
 }
 
 impl EventHandler for Game {
-    fn update(&mut self, _context: &mut Context) -> GameResult {
-        self.world.update();
+    fn update(&mut self, context: &mut Context) -> GameResult {
+        let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+        while timer::check_update_time(context, constants::UPDATES_PER_SECOND) {
+            self.world.update(delta);
+            self.dialogbox.update(delta);
+
+            if let Some(portal) = self.world.get_entered_portal() {
+                match self.world.travel(context, &portal) {
+                    Ok(()) => self.camera.give_map_dimensions(self.world.get_dimensions()),
+                    Err(error) => self.dialogbox.give_message(error.to_string()),
+                }
+            }
+        }
+
         self.camera.give_center(self.world.player.get_position());
+        self.world.give_view(self.camera.get_view());
 
         if !self.world.player_in_talking_range() {
-            self.dialogbox.populate_display(None);
+            self.dialogbox
+                .populate_display(None, &mut self.world.gamestate);
         }
 
-        self.dialogbox.update();
         Ok(())
     }
 
@@ -52,7 +105,9 @@ impl EventHandler for Game {
         graphics::draw(
             context,
             &self.spritebatch,
-            DrawParam::default().dest(self.camera.draw),
+            DrawParam::default()
+                .dest(self.camera.draw)
+                .color(self.world.get_clock().get_tint()),
         )?;
 
         self.dialogbox.draw(context)?;
@@ -65,7 +120,9 @@ impl EventHandler for Game {
     }
 
     fn key_up_event(&mut self, _: &mut Context, keycode: KeyCode, _: KeyMods) {
-        self.world.give_key_up(keycode);
+        if let Some(command) = self.bindings.get_command(keycode) {
+            self.world.give_command_up(command);
+        }
     }
 
     fn key_down_event(
@@ -75,15 +132,35 @@ impl EventHandler for Game {
         _: KeyMods,
         repeat: bool,
     ) {
-        if !repeat {
-            match keycode {
-                KeyCode::Q => context.continuing = false,
-                KeyCode::E => self.dialogbox.populate_display(self.world.get_dialogtree()),
-                KeyCode::J => self.dialogbox.next_response(),
-                KeyCode::K => self.dialogbox.prev_response(),
-                KeyCode::Return => self.dialogbox.choose_reponse(),
-                _ => self.world.give_key_down(keycode),
-            }
+        if repeat {
+            return;
+        }
+
+        if let Some(command) = self.bindings.get_command(keycode) {
+            self.give_command_down(context, command);
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, context: &mut Context, button: Button, _: GamepadId) {
+        if let Some(command) = self.bindings.get_button_command(button) {
+            self.give_command_down(context, command);
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _: &mut Context, button: Button, _: GamepadId) {
+        if let Some(command) = self.bindings.get_button_command(button) {
+            self.world.give_command_up(command);
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, context: &mut Context, axis: Axis, value: f32, _: GamepadId) {
+        // pressing before releasing turns a diagonal into its neighbour without stopping
+        let (pressed, released) = self.stick.give_axis(axis, value);
+        for command in pressed {
+            self.give_command_down(context, command);
+        }
+        for command in released {
+            self.world.give_command_up(command);
         }
     }
 }
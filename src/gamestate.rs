@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::clock::Clock;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Flag(String),
+    NotFlag(String),
+    Item(String),
+    NotItem(String),
+    Quest(String),
+    NotQuest(String),
+    Hours(f32, f32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    SetFlag(String),
+    ClearFlag(String),
+    GiveItem(String),
+    TakeItem(String),
+    StartQuest(String),
+    EndConversation,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    flags: HashSet<String>,
+    items: HashMap<String, usize>,
+    quests: HashSet<String>,
+    #[serde(default)]
+    pub clock: Clock,
+}
+
+impl GameState {
+    pub fn new() -> GameState {
+        GameState::default()
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    pub fn give_flag(&mut self, flag: &str) {
+        self.flags.insert(flag.to_string());
+    }
+
+    pub fn clear_flag(&mut self, flag: &str) {
+        self.flags.remove(flag);
+    }
+
+    pub fn get_item_count(&self, item: &str) -> usize {
+        self.items.get(item).cloned().unwrap_or(0)
+    }
+
+    pub fn give_item(&mut self, item: &str) {
+        *self.items.entry(item.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn take_item(&mut self, item: &str) {
+        if let Some(count) = self.items.get_mut(item) {
+            *count -= 1;
+            if *count == 0 {
+                self.items.remove(item);
+            }
+        }
+    }
+
+    pub fn has_quest(&self, quest: &str) -> bool {
+        self.quests.contains(quest)
+    }
+
+    pub fn give_quest(&mut self, quest: &str) {
+        self.quests.insert(quest.to_string());
+    }
+
+    pub fn check(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Flag(flag) => self.has_flag(flag),
+            Condition::NotFlag(flag) => !self.has_flag(flag),
+            Condition::Item(item) => self.get_item_count(item) > 0,
+            Condition::NotItem(item) => self.get_item_count(item) == 0,
+            Condition::Quest(quest) => self.has_quest(quest),
+            Condition::NotQuest(quest) => !self.has_quest(quest),
+            Condition::Hours(from, to) => self.clock.is_between(*from, *to),
+        }
+    }
+
+    pub fn check_all(&self, conditions: &[Condition]) -> bool {
+        conditions.iter().all(|condition| self.check(condition))
+    }
+
+    // returns false when the effect ends the current conversation
+    pub fn apply(&mut self, effect: &Effect) -> bool {
+        match effect {
+            Effect::SetFlag(flag) => self.give_flag(flag),
+            Effect::ClearFlag(flag) => self.clear_flag(flag),
+            Effect::GiveItem(item) => self.give_item(item),
+            Effect::TakeItem(item) => self.take_item(item),
+            Effect::StartQuest(quest) => self.give_quest(quest),
+            Effect::EndConversation => return false,
+        }
+        true
+    }
+
+    pub fn apply_all(&mut self, effects: &[Effect]) -> bool {
+        let mut continues = true;
+        for effect in effects.iter() {
+            continues &= self.apply(effect);
+        }
+        continues
+    }
+}
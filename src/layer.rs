@@ -1,39 +1,130 @@
-use ggez::graphics::spritebatch::SpriteBatch;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use ggez::graphics::{spritebatch::SpriteBatch, Rect};
+use std::io::Read;
+use std::ops::Range;
 
 use crate::cell::Cell;
-use crate::entity::Operable;
+use crate::chunk::Chunk;
+use crate::constants;
+use crate::error::Error;
 use crate::tileset::Tileset;
+use crate::xmlelements::parse_value;
 
 #[derive(Debug, Clone)]
 pub struct Layer {
-    pub cells: Vec<Cell>,
-    dimensions: (usize, usize),
+    pub name: String,
+    // drawn over the entities, like tree tops and roofs
+    pub above: bool,
+    chunks: Vec<Chunk>,
+    // the columns and rows of cells in view, none until a view is given
+    visible: (Range<usize>, Range<usize>),
 }
 
-impl Operable for Layer {
-    fn update(&mut self) {
-        for cell in self.cells.iter_mut() {
-            cell.update();
+impl Layer {
+    pub fn new(name: &str, chunks: Vec<Chunk>, tileset: &Tileset) -> Result<Layer, Error> {
+        for id in chunks.iter().flat_map(|chunk| chunk.get_ids()) {
+            tileset.get_tile(*id)?;
         }
+
+        Ok(Layer {
+            name: name.to_string(),
+            above: false,
+            chunks,
+            visible: (0..0, 0..0),
+        })
     }
 
-    fn draw(&self, spritebatch: &mut SpriteBatch) {
-        for cell in self.cells.iter() {
-            cell.draw(spritebatch);
+    // the tileset animates the cells, all cells of a tile share its timeline
+    pub fn draw(&self, spritebatch: &mut SpriteBatch, tileset: &Tileset) {
+        for cell in self.get_visible_cells() {
+            tileset.draw_tile(spritebatch, cell.id, cell.destination, cell.phase);
         }
     }
-}
 
-impl Layer {
-    pub fn new(text: &str, tileset: &Tileset, dimensions: (usize, usize)) -> Layer {
-        Layer {
-            cells: text
-                .replace("\n", "")
+    // only cells inside the view, plus a margin, are drawn, and only chunks near it are kept
+    pub fn give_view(&mut self, view: Rect) {
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+
+        let tiles = |from: f32, to: f32, size: f32, margin: usize| {
+            let first = ((from / size).floor() - margin as f32).max(0.0) as usize;
+            let last = ((to / size).ceil() + margin as f32).max(0.0) as usize;
+            first..last
+        };
+
+        self.visible = (
+            tiles(view.x, view.right(), pixel_width, constants::VIEW_MARGIN),
+            tiles(view.y, view.bottom(), pixel_height, constants::VIEW_MARGIN),
+        );
+
+        let margin = constants::CHUNK_SIZE * constants::RESIDENT_CHUNKS;
+        let (columns, rows) = (
+            tiles(view.x, view.right(), pixel_width, margin),
+            tiles(view.y, view.bottom(), pixel_height, margin),
+        );
+        for chunk in self.chunks.iter_mut() {
+            let resident = chunk.overlaps(&columns, &rows);
+            chunk.give_resident(resident);
+        }
+    }
+
+    pub fn get_visible_cells(&self) -> impl Iterator<Item = &Cell> {
+        let (columns, rows) = &self.visible;
+        self.chunks
+            .iter()
+            .filter(move |chunk| chunk.overlaps(columns, rows))
+            .flat_map(|chunk| chunk.get_resident_cells().iter())
+            .filter(move |cell| columns.contains(&cell.tile.0) && rows.contains(&cell.tile.1))
+    }
+
+    // every cell of the layer, whether its chunk is resident or not
+    pub fn get_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.chunks.iter().flat_map(Chunk::get_cells)
+    }
+
+    pub fn get_chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    pub fn decode(text: &str, encoding: &str, compression: &str) -> Result<Vec<usize>, Error> {
+        match encoding {
+            "base64" => {
+                let bytes =
+                    base64::decode(text.trim()).map_err(|e| Error::BadLayerData(e.to_string()))?;
+
+                let bytes = match compression {
+                    "zlib" => Layer::decompress(ZlibDecoder::new(bytes.as_slice()))?,
+                    "gzip" => Layer::decompress(GzDecoder::new(bytes.as_slice()))?,
+                    "" => bytes,
+                    _ => {
+                        return Err(Error::BadLayerData(format!(
+                            "unsupported compression {}",
+                            compression
+                        )))
+                    }
+                };
+
+                Ok(bytes
+                    .chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                    .collect())
+            }
+            "csv" => text
                 .split(',')
-                .enumerate()
-                .map(|(i, s)| Cell::new(s, i, tileset, dimensions))
+                .map(|s| parse_value("tile id", s.trim()))
                 .collect(),
-            dimensions,
+            _ => Err(Error::BadLayerData(format!(
+                "unsupported encoding {}",
+                encoding
+            ))),
         }
     }
+
+    fn decompress(mut reader: impl Read) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::BadLayerData(e.to_string()))?;
+        Ok(bytes)
+    }
 }
@@ -1,14 +1,25 @@
 pub mod animations;
+pub mod bindings;
 pub mod camera;
 pub mod cell;
+pub mod character;
+pub mod chunk;
+pub mod clock;
+pub mod collision;
 pub mod constants;
 pub mod dialogbox;
 pub mod entity;
+pub mod error;
 pub mod game;
+pub mod gamestate;
 pub mod layer;
 pub mod map;
+pub mod navigation;
 pub mod npc;
+pub mod object;
 pub mod player;
+pub mod resources;
+pub mod save;
 pub mod tile;
 pub mod tileset;
 pub mod world;
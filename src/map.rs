@@ -1,88 +1,204 @@
-use ggez::filesystem::File;
-use ggez::graphics::spritebatch::SpriteBatch;
+use ggez::graphics::{spritebatch::SpriteBatch, Rect};
 use ggez::nalgebra::Point2;
-use std::collections::HashMap;
-use xml::reader::XmlEvent::Characters;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::rc::Rc;
+use xml::reader::XmlEvent::{Characters, EndElement, StartElement};
 
+use crate::chunk::Chunk;
+use crate::collision::Collision;
 use crate::constants;
-use crate::entity::Operable;
+use crate::error::Error;
 use crate::layer::Layer;
-use crate::npc::Character;
+use crate::navigation::Navigation;
+use crate::object::{Object, Shape};
 use crate::tile::Tile;
 use crate::tileset::Tileset;
 use crate::xmlelements::XMLElements;
 
+// a block of tile ids as stored in the file, at the tile position of its top left corner
+type Block = ((isize, isize), (usize, usize), Vec<usize>);
+
 #[derive(Clone)]
 pub struct Map {
     dimensions: (usize, usize),
     layers: Vec<Layer>,
-    spawns: Vec<(String, Point2<f32>)>,
+    spawns: Vec<Spawn>,
+    objects: Vec<Object>,
+    portals: Vec<(Object, Portal)>,
+    // shared with every entity on the map
+    collision: Rc<Collision>,
+    navigation: Rc<Navigation>,
 }
 
-impl Operable for Map {
-    fn draw(&self, spritebatch: &mut SpriteBatch) {
-        for layer in self.layers.iter() {
-            layer.draw(spritebatch);
-        }
-    }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spawn {
+    // stays the same while the map does, saves key their npcs by it
+    pub id: String,
+    pub character: String,
+    pub position: Point2<f32>,
+    pub name: Option<String>,
+    pub dialogtree: Option<String>,
+    pub routine: Option<String>,
+}
 
-    fn update(&mut self) {
-        for layer in self.layers.iter_mut() {
-            layer.update();
-        }
-    }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Portal {
+    pub map: String,
+    pub destination: Option<String>,
 }
 
 impl Map {
-    pub fn new(file: File, tileset: &Tileset) -> Map {
-        let elements = XMLElements::new(file);
+    pub fn new<R: Read>(reader: R, tileset: &Tileset) -> Result<Map, Error> {
+        let elements = XMLElements::new(reader)?;
 
         let dimensions = (
-            elements
-                .get_element_attribute("map", "width")
-                .unwrap()
-                .parse()
-                .unwrap(),
-            elements
-                .get_element_attribute("map", "height")
-                .unwrap()
-                .parse()
-                .unwrap(),
+            elements.parse_element_attribute("map", "width")?,
+            elements.parse_element_attribute("map", "height")?,
         );
+        let infinite = elements
+            .get_element_attribute("map", "infinite")
+            .is_ok_and(|infinite| infinite == "1");
 
-        let layers: Vec<Layer> = elements
-            .events
-            .iter()
-            .filter_map(|e| {
-                if let Characters(text) = e {
-                    Some(Layer::new(text, tileset, dimensions))
-                } else {
-                    None
+        let mut group_name = String::new();
+        let mut encoding = String::new();
+        let mut compression = String::new();
+        let mut in_data = false;
+        let mut chunk = None;
+        let mut layer_blocks: Vec<(String, bool, Vec<Block>)> = Vec::new();
+        let mut objects = Vec::new();
+        for (index, event) in elements.events.iter().enumerate() {
+            match event {
+                StartElement { name, .. } => match name.local_name.as_str() {
+                    "layer" => {
+                        let properties = elements.get_children(index, "property");
+                        layer_blocks.push((
+                            XMLElements::get_attribute(event, "name").unwrap_or_default(),
+                            XMLElements::get_attribute_value(&properties, "above").as_deref()
+                                == Some("true"),
+                            Vec::new(),
+                        ));
+                    }
+                    "objectgroup" => {
+                        group_name = XMLElements::get_attribute(event, "name").unwrap_or_default()
+                    }
+                    "object" => objects.push(Object::new(&elements, index, &group_name)?),
+                    "data" => {
+                        encoding =
+                            XMLElements::get_attribute(event, "encoding").unwrap_or_default();
+                        compression =
+                            XMLElements::get_attribute(event, "compression").unwrap_or_default();
+                        in_data = true;
+                    }
+                    "chunk" => {
+                        chunk = Some((
+                            (
+                                XMLElements::parse_attribute(event, "x")?,
+                                XMLElements::parse_attribute(event, "y")?,
+                            ),
+                            (
+                                XMLElements::parse_attribute(event, "width")?,
+                                XMLElements::parse_attribute(event, "height")?,
+                            ),
+                        ))
+                    }
+                    _ => (),
+                },
+                EndElement { name } if name.local_name == "data" => in_data = false,
+                EndElement { name } if name.local_name == "chunk" => chunk = None,
+                Characters(text) if in_data => {
+                    let ids = Layer::decode(text, &encoding, &compression)?;
+                    let (position, size) = chunk.unwrap_or(((0, 0), dimensions));
+                    if let Some((_, _, blocks)) = layer_blocks.last_mut() {
+                        blocks.push((position, size, ids));
+                    }
                 }
-            })
-            .collect();
+                _ => (),
+            }
+        }
+
+        // infinite maps start at their top left chunk, which may lie at negative coordinates
+        let blocks = || layer_blocks.iter().flat_map(|(_, _, blocks)| blocks.iter());
+        let (origin, dimensions) = if infinite && blocks().next().is_some() {
+            let left = blocks().map(|((x, _), _, _)| *x).min().unwrap_or(0);
+            let top = blocks().map(|((_, y), _, _)| *y).min().unwrap_or(0);
+            let right = blocks().map(|((x, _), (w, _), _)| x + *w as isize).max();
+            let bottom = blocks().map(|((_, y), (_, h), _)| y + *h as isize).max();
+            (
+                (left, top),
+                (
+                    (right.unwrap_or(0) - left) as usize,
+                    (bottom.unwrap_or(0) - top) as usize,
+                ),
+            )
+        } else {
+            ((0, 0), dimensions)
+        };
+
+        for object in objects.iter_mut() {
+            object.translate(
+                -origin.0 as f32 * constants::TILE_WIDTH * constants::TILE_SCALE,
+                -origin.1 as f32 * constants::TILE_HEIGHT * constants::TILE_SCALE,
+            );
+        }
+
+        let mut layers = Vec::new();
+        for (name, above, blocks) in layer_blocks.iter() {
+            let chunks = blocks
+                .iter()
+                .flat_map(|((x, y), size, ids)| {
+                    Chunk::split(
+                        ((x - origin.0) as usize, (y - origin.1) as usize),
+                        *size,
+                        ids,
+                    )
+                })
+                .collect();
+            let mut layer = Layer::new(name, chunks, tileset)?;
+            layer.above = *above;
+            layers.push(layer);
+        }
+
+        let (collision_layers, layers): (Vec<Layer>, Vec<Layer>) = layers
+            .into_iter()
+            .partition(|layer| layer.name == constants::COLLISION_LAYER);
 
         let spawns = Map::find_spawn_points(&layers, tileset.get_spawn_tiles());
+        let portals = Map::find_portals(&layers, tileset.get_portal_tiles(), &objects);
+        let collision = Rc::new(Collision::new(
+            &layers,
+            &collision_layers,
+            tileset,
+            dimensions,
+        ));
+        let navigation = Rc::new(Navigation::new(&collision));
 
-        Map {
+        Ok(Map {
             layers,
             dimensions,
             spawns,
-        }
+            objects,
+            portals,
+            collision,
+            navigation,
+        })
     }
 
-    fn find_spawn_points(
-        layers: &[Layer],
-        spawn_tiles: HashMap<usize, Tile>,
-    ) -> Vec<(String, Point2<f32>)> {
+    fn find_spawn_points(layers: &[Layer], spawn_tiles: HashMap<usize, Tile>) -> Vec<Spawn> {
         let mut spawn_points = Vec::new();
 
         for layer in layers.iter() {
-            for cell in layer.cells.iter() {
+            for cell in layer.get_cells() {
                 for (id, tile) in spawn_tiles.iter() {
                     if id == &cell.id {
-                        spawn_points
-                            .push((tile.properties.spawn.clone().unwrap(), cell.destination));
+                        spawn_points.push(Spawn {
+                            id: format!("tile:{},{}", cell.tile.0, cell.tile.1),
+                            character: tile.properties.spawn.clone().unwrap(),
+                            position: cell.destination,
+                            name: tile.properties.name.clone(),
+                            dialogtree: tile.properties.dialogtree.clone(),
+                            routine: tile.properties.routine.clone(),
+                        });
                     }
                 }
             }
@@ -91,15 +207,141 @@ impl Map {
         spawn_points
     }
 
-    pub fn get_spawn_points(&self, character: Character) -> Vec<Point2<f32>> {
+    fn find_portals(
+        layers: &[Layer],
+        portal_tiles: HashMap<usize, Tile>,
+        objects: &[Object],
+    ) -> Vec<(Object, Portal)> {
+        let mut portals: Vec<(Object, Portal)> = objects
+            .iter()
+            .filter_map(|object| {
+                let portal = Portal {
+                    map: object.properties.get("portal")?.clone(),
+                    destination: object.properties.get("destination").cloned(),
+                };
+                Some((object.clone(), portal))
+            })
+            .collect();
+
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+
+        for layer in layers.iter() {
+            for cell in layer.get_cells() {
+                if let Some(tile) = portal_tiles.get(&cell.id) {
+                    let area = Object {
+                        id: 0,
+                        name: String::new(),
+                        kind: "portal".to_string(),
+                        group: layer.name.clone(),
+                        position: Point2::new(
+                            cell.destination.x - pixel_width / 2.0,
+                            cell.destination.y - pixel_height / 2.0,
+                        ),
+                        shape: Shape::Rectangle(pixel_width, pixel_height),
+                        properties: HashMap::new(),
+                    };
+                    let portal = Portal {
+                        map: tile.properties.portal.clone().unwrap(),
+                        destination: tile.properties.destination.clone(),
+                    };
+                    portals.push((area, portal));
+                }
+            }
+        }
+
+        portals
+    }
+
+    pub fn get_spawns(&self) -> Vec<Spawn> {
+        let object_spawns = self.objects.iter().filter_map(|object| {
+            Some(Spawn {
+                id: format!("object:{}", object.id),
+                character: object.properties.get("spawn")?.clone(),
+                position: object.get_center(),
+                name: object.properties.get("name").cloned(),
+                dialogtree: object.properties.get("dialogtree").cloned(),
+                routine: object.properties.get("routine").cloned(),
+            })
+        });
+
         self.spawns
             .clone()
             .into_iter()
-            .filter(|s| s.0 == character.to_str())
-            .map(|s| s.1)
+            .chain(object_spawns)
+            .collect()
+    }
+
+    pub fn get_spawn_points(&self, character: &str) -> Vec<Point2<f32>> {
+        self.get_spawns()
+            .into_iter()
+            .filter(|spawn| spawn.character == character)
+            .map(|spawn| spawn.position)
+            .collect()
+    }
+
+    pub fn draw_below(&self, spritebatch: &mut SpriteBatch, tileset: &Tileset) {
+        for layer in self.layers.iter().filter(|layer| !layer.above) {
+            layer.draw(spritebatch, tileset);
+        }
+    }
+
+    pub fn draw_above(&self, spritebatch: &mut SpriteBatch, tileset: &Tileset) {
+        for layer in self.layers.iter().filter(|layer| layer.above) {
+            layer.draw(spritebatch, tileset);
+        }
+    }
+
+    pub fn give_view(&mut self, view: Rect) {
+        for layer in self.layers.iter_mut() {
+            layer.give_view(view);
+        }
+    }
+
+    pub fn get_layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    // every tile id placed on the map's layers, flip flags included
+    pub fn get_tile_ids(&self) -> HashSet<usize> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.get_chunks())
+            .flat_map(|chunk| chunk.get_ids())
+            .cloned()
             .collect()
     }
 
+    pub fn get_objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    pub fn get_named_point(&self, name: &str) -> Option<Point2<f32>> {
+        self.objects
+            .iter()
+            .find(|object| object.name == name)
+            .map(|object| object.get_center())
+    }
+
+    pub fn get_portal(&self, position: Point2<f32>) -> Option<Portal> {
+        self.portals
+            .iter()
+            .find(|(area, _)| area.contains(position))
+            .map(|(_, portal)| portal.clone())
+    }
+
+    pub fn collides(&self, position: Point2<f32>) -> bool {
+        self.collision.collides(position)
+    }
+
+    pub fn get_collision(&self) -> Rc<Collision> {
+        Rc::clone(&self.collision)
+    }
+
+    pub fn get_navigation(&self) -> Rc<Navigation> {
+        Rc::clone(&self.navigation)
+    }
+
     pub fn get_dimensions(&self) -> (f32, f32) {
         (
             (constants::TILE_WIDTH * constants::TILE_SCALE) * self.dimensions.0 as f32,
@@ -0,0 +1,199 @@
+use ggez::nalgebra::Point2;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::collision::Collision;
+use crate::constants;
+
+const STRAIGHT_COST: usize = 10;
+const DIAGONAL_COST: usize = 14;
+
+#[derive(Debug, Clone, Default)]
+pub struct Navigation {
+    // only chunks near solid tiles have blocked tiles, the others are walkable everywhere
+    blocked: HashMap<(usize, usize), Vec<bool>>,
+    dimensions: (usize, usize),
+    size: (f32, f32),
+}
+
+impl Navigation {
+    pub fn new(collision: &Collision) -> Navigation {
+        let dimensions = collision.get_tile_dimensions();
+
+        // a hitbox can reach into the chunks around a solid one
+        let mut chunks = HashSet::new();
+        for &(x, y) in collision.get_solid_chunks() {
+            for cy in y.saturating_sub(1)..=y + 1 {
+                for cx in x.saturating_sub(1)..=x + 1 {
+                    chunks.insert((cx, cy));
+                }
+            }
+        }
+
+        let mut blocked = HashMap::new();
+        for (cx, cy) in chunks {
+            let mut tiles = Vec::with_capacity(constants::CHUNK_SIZE * constants::CHUNK_SIZE);
+            for y in 0..constants::CHUNK_SIZE {
+                for x in 0..constants::CHUNK_SIZE {
+                    let tile = (
+                        cx * constants::CHUNK_SIZE + x,
+                        cy * constants::CHUNK_SIZE + y,
+                    );
+                    tiles.push(collision.collides(Navigation::get_position(tile)));
+                }
+            }
+            if tiles.iter().any(|&tile| tile) {
+                blocked.insert((cx, cy), tiles);
+            }
+        }
+
+        Navigation {
+            blocked,
+            dimensions,
+            size: collision.get_dimensions(),
+        }
+    }
+
+    pub fn is_walkable(&self, tile: (usize, usize)) -> bool {
+        if tile.0 >= self.dimensions.0 || tile.1 >= self.dimensions.1 {
+            return false;
+        }
+
+        // entities are clamped to the map, so tiles they can't stand in are skipped
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+        let position = Navigation::get_position(tile);
+        if position.x + pixel_width > self.size.0 || position.y + pixel_height > self.size.1 {
+            return false;
+        }
+
+        let (chunk, index) = Collision::get_chunk(tile);
+        !self
+            .blocked
+            .get(&chunk)
+            .is_some_and(|blocked| blocked[index])
+    }
+
+    pub fn get_tile(&self, position: Point2<f32>) -> Option<(usize, usize)> {
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+
+        // entities stand on their feet, at the bottom of their hitbox
+        let feet_y = position.y + pixel_height * (1.0 - constants::HITBOX_HEIGHT / 2.0);
+        if position.x < 0.0 || feet_y < 0.0 {
+            return None;
+        }
+
+        let tile = (
+            (position.x / pixel_width) as usize,
+            (feet_y / pixel_height) as usize,
+        );
+        if tile.0 < self.dimensions.0 && tile.1 < self.dimensions.1 {
+            Some(tile)
+        } else {
+            None
+        }
+    }
+
+    // the entity position whose feet stand inside the tile
+    pub fn get_position(tile: (usize, usize)) -> Point2<f32> {
+        let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+        let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+        Point2::new(
+            (tile.0 as f32 + 0.5) * pixel_width,
+            tile.1 as f32 * pixel_height,
+        )
+    }
+
+    // gives up after visiting PATH_NODES tiles, so far off or walled in destinations stay cheap
+    pub fn find_path(&self, from: Point2<f32>, to: Point2<f32>) -> Option<Vec<Point2<f32>>> {
+        let start = self.get_tile(from)?;
+        let goal = self.get_tile(to)?;
+        if !self.is_walkable(goal) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::new();
+        let mut costs = HashMap::new();
+        open.push(Reverse((self.estimate(start, goal), start)));
+        costs.insert(start, 0);
+
+        let mut visited = 0;
+        while let Some(Reverse((_, tile))) = open.pop() {
+            visited += 1;
+            if visited > constants::PATH_NODES {
+                return None;
+            }
+
+            if tile == goal {
+                let mut path = vec![Navigation::get_position(tile)];
+                let mut current = tile;
+                while let Some(previous) = came_from.get(&current) {
+                    current = *previous;
+                    if current != start {
+                        path.push(Navigation::get_position(current));
+                    }
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cost = costs[&tile];
+            for (neighbour, step) in self.get_neighbours(tile) {
+                let new_cost = cost + step;
+                let improved = match costs.get(&neighbour) {
+                    Some(previous_cost) => new_cost < *previous_cost,
+                    None => true,
+                };
+                if improved {
+                    costs.insert(neighbour, new_cost);
+                    came_from.insert(neighbour, tile);
+                    open.push(Reverse((
+                        new_cost + self.estimate(neighbour, goal),
+                        neighbour,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn get_neighbours(&self, tile: (usize, usize)) -> Vec<((usize, usize), usize)> {
+        let mut neighbours = Vec::new();
+
+        for dy in -1..=1_isize {
+            for dx in -1..=1_isize {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+
+                let x = tile.0 as isize + dx;
+                let y = tile.1 as isize + dy;
+                if x < 0 || y < 0 || !self.is_walkable((x as usize, y as usize)) {
+                    continue;
+                }
+
+                if dx != 0 && dy != 0 {
+                    // don't cut corners, the hitbox would clip the wall
+                    let horizontal = (x as usize, tile.1);
+                    let vertical = (tile.0, y as usize);
+                    if self.is_walkable(horizontal) && self.is_walkable(vertical) {
+                        neighbours.push(((x as usize, y as usize), DIAGONAL_COST));
+                    }
+                } else {
+                    neighbours.push(((x as usize, y as usize), STRAIGHT_COST));
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    fn estimate(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        let dx = (from.0 as isize - to.0 as isize).unsigned_abs();
+        let dy = (from.1 as isize - to.1 as isize).unsigned_abs();
+        STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+    }
+}
@@ -1,39 +1,36 @@
 use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::nalgebra::{distance, Point2};
-use ggez::Context;
 use rand::Rng;
+use std::collections::HashMap;
 use std::f32::consts::PI;
-use std::time::Instant;
+use std::rc::Rc;
 
 use crate::animations::Animations;
+use crate::character::{Character, Routine};
 use crate::constants;
 use crate::dialogbox::DialogTree;
 use crate::entity::{Action, Entity, Operable};
-use crate::map::Map;
+use crate::error::Error;
+use crate::map::{Map, Spawn};
+use crate::navigation::Navigation;
+use crate::resources::Resources;
+use crate::save::{BehaviorState, NPCState};
 use crate::tileset::Tileset;
 
-#[derive(Debug, Clone, Copy)]
-pub enum Character {
-    Player,
-    Peasant,
-}
-
-impl Character {
-    pub fn to_str(&self) -> &str {
-        match self {
-            Character::Player => "player",
-            Character::Peasant => "peasant",
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct NPC {
     pub entity: Entity,
+    id: String,
     behavior: Behavior,
     animations: Animations,
     dialogtree: DialogTree,
     character: Character,
+    navigation: Rc<Navigation>,
+    routine: Routine,
+    active: Routine,
+    points: HashMap<String, Point2<f32>>,
+    patrol: usize,
+    hour: f32,
 }
 
 impl Operable for NPC {
@@ -41,47 +38,139 @@ impl Operable for NPC {
         self.animations.draw(spritebatch, self.entity.position);
     }
 
-    fn update(&mut self) {
-        match self.behavior {
-            Behavior::Wandering(destination) => self.move_torwards(destination),
-            Behavior::Waiting(time) => self.wait(time),
-            Behavior::Talking => (),
+    fn update(&mut self, delta: f32) {
+        if !self.is_talking() && self.routine.at(self.hour) != &self.active {
+            self.active = self.routine.at(self.hour).clone();
+            self.resume_routine();
+        }
+
+        if let Behavior::Waiting(waited) = self.behavior {
+            self.wait(waited + delta);
         }
-        self.entity.update();
-        self.animations.update(&self.entity.action);
+        if self.get_path().is_some() {
+            self.follow_path();
+        }
+
+        let position = self.entity.position;
+        self.entity.update(delta);
+        if self.get_path().is_some() && position == self.entity.position {
+            self.entity.action = self.entity.action.get_idle();
+            self.behavior = Behavior::Waiting(0.0);
+        }
+
+        self.animations.update(&self.entity.action, delta);
     }
 }
 
 impl NPC {
     pub fn new(
-        character: Character,
-        context: &mut Context,
+        character: &Character,
+        resources: &mut dyn Resources,
         tileset: &Tileset,
-        spawn: Point2<f32>,
-        map_dimensions: (f32, f32),
-    ) -> NPC {
-        NPC {
-            character,
-            dialogtree: DialogTree::new(context, character),
-            entity: Entity::new(spawn, map_dimensions),
-            behavior: Behavior::Wandering(random_nearby_point(spawn, constants::WANDER_DISTANCE)),
-            animations: Animations::new(tileset),
+        spawn: &Spawn,
+        map: &Map,
+        hour: f32,
+    ) -> Result<NPC, Error> {
+        let dialogtree = spawn
+            .dialogtree
+            .as_deref()
+            .unwrap_or_else(|| character.get_dialogtree());
+
+        let mut dialogtree = DialogTree::new(resources, dialogtree)?;
+        dialogtree.give_speaker(spawn.name.as_deref().unwrap_or(&character.name));
+
+        let routine = match &spawn.routine {
+            Some(routine) => Routine::parse(routine)?,
+            None => character.routine.clone(),
+        };
+
+        let mut points = HashMap::new();
+        for point in routine.get_points() {
+            let position = map
+                .get_named_point(point)
+                .ok_or_else(|| Error::MissingPoint(point.to_string()))?;
+            points.insert(point.to_string(), position);
+        }
+
+        let mut npc = NPC {
+            dialogtree,
+            id: spawn.id.clone(),
+            entity: Entity::new(spawn.position, character.speed, map.get_collision()),
+            behavior: Behavior::Standing,
+            animations: Animations::new(tileset, character.get_entity())?,
+            character: character.clone(),
+            navigation: map.get_navigation(),
+            active: routine.at(hour).clone(),
+            routine,
+            points,
+            patrol: 0,
+            hour,
+        };
+        npc.resume_routine();
+
+        Ok(npc)
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn give_hour(&mut self, hour: f32) {
+        self.hour = hour;
+    }
+
+    fn get_path(&self) -> Option<&Vec<Point2<f32>>> {
+        match &self.behavior {
+            Behavior::Wandering(path) | Behavior::Patrolling(path) | Behavior::Travelling(path) => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    fn follow_path(&mut self) {
+        let position = self.entity.position;
+
+        let waypoint = match &mut self.behavior {
+            Behavior::Wandering(path) | Behavior::Patrolling(path) | Behavior::Travelling(path) => {
+                while !path.is_empty()
+                    && distance(&position, &path[0]) < constants::WAYPOINT_DISTANCE
+                {
+                    path.remove(0);
+                }
+                path.first().cloned()
+            }
+            _ => None,
+        };
+
+        match waypoint {
+            Some(waypoint) => self.move_torwards(waypoint),
+            None => self.arrive(),
         }
     }
 
+    fn arrive(&mut self) {
+        self.entity.action = self.entity.action.get_idle();
+        self.behavior = match self.behavior {
+            Behavior::Patrolling(_) => {
+                self.patrol += 1;
+                Behavior::Waiting(0.0)
+            }
+            Behavior::Travelling(_) => Behavior::Standing,
+            _ => Behavior::Waiting(0.0),
+        };
+    }
+
     fn move_torwards(&mut self, destination: Point2<f32>) {
         let position = self.entity.position;
 
-        if distance(&position, &destination) < constants::INTERACT_DISTANCE {
-            self.entity.action = Action::IdleRight;
-            self.behavior = Behavior::Waiting(Instant::now());
-        } else if (position.x - destination.x).abs() < constants::INTERACT_DISTANCE {
+        if (position.x - destination.x).abs() < constants::WAYPOINT_DISTANCE {
             if position.y > destination.y {
                 self.entity.action = Action::MovingUp;
             } else {
                 self.entity.action = Action::MovingDown;
             }
-        } else if (position.y - destination.y).abs() < constants::INTERACT_DISTANCE {
+        } else if (position.y - destination.y).abs() < constants::WAYPOINT_DISTANCE {
             if position.x > destination.x {
                 self.entity.action = Action::MovingLeft;
             } else {
@@ -102,15 +191,49 @@ impl NPC {
         }
     }
 
-    fn wait(&mut self, start: Instant) {
-        if start.elapsed().as_secs() > constants::WAIT_TIME {
-            self.behavior = Behavior::Wandering(random_nearby_point(
-                self.entity.spawn,
-                constants::WANDER_DISTANCE,
-            ));
+    fn wait(&mut self, waited: f32) {
+        if waited > constants::WAIT_TIME {
+            self.resume_routine();
+        } else {
+            self.behavior = Behavior::Waiting(waited);
         }
     }
 
+    fn resume_routine(&mut self) {
+        self.behavior = match self.active.clone() {
+            Routine::Wander => self.wander(),
+            Routine::Stand | Routine::Schedule(_) => Behavior::Standing,
+            Routine::Post(point) => match self.find_path_to(&point) {
+                Some(path) => Behavior::Travelling(path),
+                None => Behavior::Waiting(0.0),
+            },
+            Routine::Patrol(points) if !points.is_empty() => {
+                match self.find_path_to(&points[self.patrol % points.len()]) {
+                    Some(path) => Behavior::Patrolling(path),
+                    None => Behavior::Waiting(0.0),
+                }
+            }
+            Routine::Patrol(_) => Behavior::Standing,
+        };
+    }
+
+    fn find_path_to(&self, point: &str) -> Option<Vec<Point2<f32>>> {
+        self.navigation
+            .find_path(self.entity.position, *self.points.get(point)?)
+    }
+
+    // only destinations the navigation grid can reach are picked
+    fn wander(&self) -> Behavior {
+        for _ in 0..constants::WANDER_ATTEMPTS {
+            let destination = random_nearby_point(self.entity.spawn, self.character.wander_radius);
+            if let Some(path) = self.navigation.find_path(self.entity.position, destination) {
+                return Behavior::Wandering(path);
+            }
+        }
+
+        Behavior::Waiting(0.0)
+    }
+
     pub fn get_dialogtree(&mut self) -> DialogTree {
         self.behavior = Behavior::Talking;
         self.dialogtree.clone()
@@ -121,35 +244,96 @@ impl NPC {
     }
 
     pub fn stop_talking(&mut self) {
-        self.behavior = Behavior::Wandering(random_nearby_point(
-            self.entity.spawn,
-            constants::WANDER_DISTANCE,
-        ));
+        self.resume_routine();
+    }
+
+    pub fn save(&self) -> NPCState {
+        let destination = |path: &Vec<Point2<f32>>| {
+            path.last()
+                .map(|destination| (destination.x, destination.y))
+        };
+
+        NPCState {
+            entity: self.entity.save(),
+            behavior: match &self.behavior {
+                Behavior::Talking => BehaviorState::Talking,
+                Behavior::Waiting(waited) => BehaviorState::Waiting(*waited),
+                Behavior::Standing => BehaviorState::Standing,
+                Behavior::Wandering(path) => match destination(path) {
+                    Some(destination) => BehaviorState::Wandering(destination),
+                    None => BehaviorState::Waiting(0.0),
+                },
+                Behavior::Patrolling(path) => match destination(path) {
+                    Some(destination) => BehaviorState::Patrolling(destination),
+                    None => BehaviorState::Waiting(0.0),
+                },
+                Behavior::Travelling(path) => match destination(path) {
+                    Some(destination) => BehaviorState::Travelling(destination),
+                    None => BehaviorState::Waiting(0.0),
+                },
+            },
+            patrol: self.patrol,
+        }
+    }
+
+    pub fn load(&mut self, state: &NPCState) {
+        self.entity.load(&state.entity);
+        self.patrol = state.patrol;
+        self.active = self.routine.at(self.hour).clone();
+
+        let path = |destination: (f32, f32)| {
+            self.navigation.find_path(
+                self.entity.position,
+                Point2::new(destination.0, destination.1),
+            )
+        };
+
+        self.behavior = match state.behavior {
+            BehaviorState::Talking => Behavior::Talking,
+            BehaviorState::Waiting(waited) => Behavior::Waiting(waited),
+            BehaviorState::Standing => Behavior::Standing,
+            BehaviorState::Wandering(destination) => {
+                path(destination).map_or(Behavior::Waiting(0.0), Behavior::Wandering)
+            }
+            BehaviorState::Patrolling(destination) => {
+                path(destination).map_or(Behavior::Waiting(0.0), Behavior::Patrolling)
+            }
+            BehaviorState::Travelling(destination) => {
+                path(destination).map_or(Behavior::Waiting(0.0), Behavior::Travelling)
+            }
+        };
     }
 
-    pub fn build_npcs(context: &mut Context, tileset: &Tileset, map: &Map) -> Vec<NPC> {
+    pub fn build_npcs(
+        resources: &mut dyn Resources,
+        tileset: &Tileset,
+        characters: &HashMap<String, Character>,
+        map: &Map,
+        hour: f32,
+    ) -> Result<Vec<NPC>, Error> {
         let mut npcs = Vec::new();
 
-        let character = Character::Peasant;
-        for point in map.get_spawn_points(character) {
-            npcs.push(NPC::new(
-                character,
-                context,
-                tileset,
-                point,
-                map.get_dimensions(),
-            ));
+        for spawn in map
+            .get_spawns()
+            .iter()
+            .filter(|spawn| spawn.character != constants::PLAYER)
+        {
+            let character = Character::get(characters, &spawn.character)?;
+            npcs.push(NPC::new(character, resources, tileset, spawn, map, hour)?);
         }
 
-        npcs
+        Ok(npcs)
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum Behavior {
     Talking,
-    Waiting(Instant),
-    Wandering(Point2<f32>),
+    Waiting(f32),
+    Standing,
+    Wandering(Vec<Point2<f32>>),
+    Patrolling(Vec<Point2<f32>>),
+    Travelling(Vec<Point2<f32>>),
 }
 
 pub fn random_nearby_point(origin: Point2<f32>, within_radius: f32) -> Point2<f32> {
@@ -0,0 +1,148 @@
+use ggez::nalgebra::Point2;
+use std::collections::HashMap;
+
+use crate::constants;
+use crate::error::Error;
+use crate::xmlelements::{parse_value, XMLElements};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Point,
+    Rectangle(f32, f32),
+    Ellipse(f32, f32),
+    Polygon(Vec<Point2<f32>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub id: usize,
+    pub name: String,
+    pub kind: String,
+    pub group: String,
+    pub position: Point2<f32>,
+    pub shape: Shape,
+    pub properties: HashMap<String, String>,
+}
+
+impl Object {
+    pub fn new(elements: &XMLElements, index: usize, group: &str) -> Result<Object, Error> {
+        let element = &elements.events[index];
+        let get_number = |attribute_name| -> Result<f32, Error> {
+            match XMLElements::get_attribute(element, attribute_name) {
+                Some(number) => {
+                    Ok(parse_value::<f32>(attribute_name, &number)? * constants::TILE_SCALE)
+                }
+                None => Ok(0.0),
+            }
+        };
+
+        let position = Point2::new(get_number("x")?, get_number("y")?);
+        let width = get_number("width")?;
+        let height = get_number("height")?;
+
+        let shape = if !elements.get_children(index, "point").is_empty() {
+            Shape::Point
+        } else if !elements.get_children(index, "ellipse").is_empty() {
+            Shape::Ellipse(width, height)
+        } else if let Some(polygon) = elements.get_children(index, "polygon").first() {
+            let points = XMLElements::require_attribute(polygon, "points")?;
+            let points: Vec<Point2<f32>> = points
+                .split_whitespace()
+                .map(
+                    |pair| match pair.split(',').collect::<Vec<&str>>().as_slice() {
+                        [x, y] => Ok(Point2::new(
+                            position.x + parse_value::<f32>("points", x)? * constants::TILE_SCALE,
+                            position.y + parse_value::<f32>("points", y)? * constants::TILE_SCALE,
+                        )),
+                        _ => Err(Error::BadValue("points".to_string(), pair.to_string())),
+                    },
+                )
+                .collect::<Result<_, _>>()?;
+            // a polygon without points has no center to spawn or stand at
+            if points.is_empty() {
+                return Err(Error::BadValue("points".to_string(), String::new()));
+            }
+            Shape::Polygon(points)
+        } else {
+            Shape::Rectangle(width, height)
+        };
+
+        let properties = elements
+            .get_children(index, "property")
+            .iter()
+            .filter_map(|property| {
+                Some((
+                    XMLElements::get_attribute(property, "name")?,
+                    XMLElements::get_attribute(property, "value")?,
+                ))
+            })
+            .collect();
+
+        Ok(Object {
+            id: XMLElements::parse_attribute(element, "id")?,
+            name: XMLElements::get_attribute(element, "name").unwrap_or_default(),
+            kind: XMLElements::get_attribute(element, "type").unwrap_or_default(),
+            group: group.to_string(),
+            position,
+            shape,
+            properties,
+        })
+    }
+
+    pub fn translate(&mut self, x: f32, y: f32) {
+        self.position.x += x;
+        self.position.y += y;
+        if let Shape::Polygon(points) = &mut self.shape {
+            for point in points.iter_mut() {
+                point.x += x;
+                point.y += y;
+            }
+        }
+    }
+
+    pub fn get_center(&self) -> Point2<f32> {
+        match &self.shape {
+            Shape::Point => self.position,
+            Shape::Rectangle(width, height) | Shape::Ellipse(width, height) => Point2::new(
+                self.position.x + width / 2.0,
+                self.position.y + height / 2.0,
+            ),
+            Shape::Polygon(points) => Point2::new(
+                points.iter().map(|p| p.x).sum::<f32>() / points.len() as f32,
+                points.iter().map(|p| p.y).sum::<f32>() / points.len() as f32,
+            ),
+        }
+    }
+
+    pub fn contains(&self, point: Point2<f32>) -> bool {
+        match &self.shape {
+            Shape::Point => false,
+            Shape::Rectangle(width, height) => {
+                point.x >= self.position.x
+                    && point.x <= self.position.x + width
+                    && point.y >= self.position.y
+                    && point.y <= self.position.y + height
+            }
+            Shape::Ellipse(width, height) => {
+                let center = self.get_center();
+                let dx = (point.x - center.x) / (width / 2.0);
+                let dy = (point.y - center.y) / (height / 2.0);
+                dx * dx + dy * dy <= 1.0
+            }
+            Shape::Polygon(points) => {
+                let mut inside = false;
+                let mut j = points.len().wrapping_sub(1);
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[j]);
+                    if (a.y > point.y) != (b.y > point.y)
+                        && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+                    {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+}
@@ -1,15 +1,21 @@
-use ggez::event::KeyCode;
 use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::nalgebra::Point2;
+use std::rc::Rc;
 
 use crate::animations::Animations;
+use crate::bindings::Command;
+use crate::character::Character;
+use crate::collision::Collision;
 use crate::entity::{Action, Entity, Operable};
+use crate::error::Error;
 use crate::tileset::Tileset;
 
 #[derive(Clone)]
 pub struct Player {
     pub entity: Entity,
     animations: Animations,
+    // held movement commands, a direction stays held while any of its keys or buttons is
+    held: Vec<Command>,
 }
 
 impl Operable for Player {
@@ -17,77 +23,63 @@ impl Operable for Player {
         self.animations.draw(spritebatch, self.get_position());
     }
 
-    fn update(&mut self) {
-        self.entity.update();
-        self.animations.update(&self.entity.action);
+    fn update(&mut self, delta: f32) {
+        self.refresh_action();
+        self.entity.update(delta);
+        self.animations.update(&self.entity.action, delta);
     }
 }
 
 impl Player {
-    pub fn new(tileset: &Tileset, spawn: Point2<f32>, map_dimensions: (f32, f32)) -> Player {
-        Player {
-            entity: Entity::new(spawn, map_dimensions),
-            animations: Animations::new(tileset),
-        }
+    pub fn new(
+        character: &Character,
+        tileset: &Tileset,
+        spawn: Point2<f32>,
+        collision: Rc<Collision>,
+    ) -> Result<Player, Error> {
+        Ok(Player {
+            entity: Entity::new(spawn, character.speed, collision),
+            animations: Animations::new(tileset, character.get_entity())?,
+            held: Vec::new(),
+        })
     }
 
     pub fn get_position(&self) -> Point2<f32> {
         self.entity.position
     }
 
-    pub fn give_key_down(&mut self, keycode: KeyCode) {
-        let original_state = self.entity.action.clone();
+    pub fn give_command_down(&mut self, command: Command) {
+        if let Command::MoveUp | Command::MoveDown | Command::MoveLeft | Command::MoveRight =
+            command
+        {
+            self.held.push(command);
+        }
+        self.refresh_action();
+    }
 
-        self.entity.action = match keycode {
-            KeyCode::W => match original_state {
-                Action::MovingLeft => Action::MovingUpLeft,
-                Action::MovingRight => Action::MovingUpRight,
-                _ => Action::MovingUp,
-            },
-            KeyCode::A => match original_state {
-                Action::MovingUp => Action::MovingUpLeft,
-                Action::MovingDown => Action::MovingDownLeft,
-                _ => Action::MovingLeft,
-            },
-            KeyCode::S => match original_state {
-                Action::MovingLeft => Action::MovingDownLeft,
-                Action::MovingRight => Action::MovingDownRight,
-                _ => Action::MovingDown,
-            },
-            KeyCode::D => match original_state {
-                Action::MovingUp => Action::MovingUpRight,
-                Action::MovingDown => Action::MovingDownRight,
-                _ => Action::MovingRight,
-            },
-            _ => original_state,
+    pub fn give_command_up(&mut self, command: Command) {
+        if let Some(index) = self.held.iter().position(|held| *held == command) {
+            self.held.remove(index);
         }
+        self.refresh_action();
     }
 
-    pub fn give_key_up(&mut self, keycode: KeyCode) {
-        let original_state = self.entity.action.clone();
+    // opposite directions cancel out, idling faces the way the player last went
+    fn refresh_action(&mut self) {
+        let is_held = |command| self.held.contains(&command) as isize;
+        let horizontal = is_held(Command::MoveRight) - is_held(Command::MoveLeft);
+        let vertical = is_held(Command::MoveDown) - is_held(Command::MoveUp);
 
-        self.entity.action = match keycode {
-            KeyCode::W => match original_state {
-                Action::MovingUpLeft => Action::MovingLeft,
-                Action::MovingUpRight => Action::MovingRight,
-                _ => Action::IdleLeft,
-            },
-            KeyCode::A => match original_state {
-                Action::MovingUpLeft => Action::MovingUp,
-                Action::MovingDownLeft => Action::MovingDown,
-                _ => Action::IdleLeft,
-            },
-            KeyCode::S => match original_state {
-                Action::MovingDownLeft => Action::MovingLeft,
-                Action::MovingDownRight => Action::MovingRight,
-                _ => Action::IdleRight,
-            },
-            KeyCode::D => match original_state {
-                Action::MovingUpRight => Action::MovingUp,
-                Action::MovingDownRight => Action::MovingDown,
-                _ => Action::IdleRight,
-            },
-            _ => original_state,
-        }
+        self.entity.action = match (horizontal, vertical) {
+            (0, 0) => self.entity.action.get_idle(),
+            (0, -1) => Action::MovingUp,
+            (0, _) => Action::MovingDown,
+            (-1, 0) => Action::MovingLeft,
+            (-1, -1) => Action::MovingUpLeft,
+            (-1, _) => Action::MovingDownLeft,
+            (_, 0) => Action::MovingRight,
+            (_, -1) => Action::MovingUpRight,
+            (_, _) => Action::MovingDownRight,
+        };
     }
 }
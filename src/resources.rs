@@ -0,0 +1,38 @@
+use ggez::filesystem;
+use ggez::Context;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::error::Error;
+
+pub trait Resources {
+    fn open(&mut self, path: &str) -> Result<Box<dyn Read>, Error>;
+}
+
+impl Resources for Context {
+    fn open(&mut self, path: &str) -> Result<Box<dyn Read>, Error> {
+        let file = filesystem::open(self, path)
+            .map_err(|e| Error::MissingResource(path.to_string(), e))?;
+        Ok(Box::new(file))
+    }
+}
+
+// resolves resource paths against a directory on disk, without a ggez context
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new(root: impl Into<PathBuf>) -> Directory {
+        Directory { root: root.into() }
+    }
+}
+
+impl Resources for Directory {
+    fn open(&mut self, path: &str) -> Result<Box<dyn Read>, Error> {
+        let file = File::open(self.root.join(path.trim_start_matches('/')))
+            .map_err(|e| Error::Io(path.to_string(), e))?;
+        Ok(Box::new(file))
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::entity::Action;
+use crate::gamestate::GameState;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Save {
+    pub map: String,
+    pub player: EntityState,
+    // keyed by the id of the npc's spawn
+    pub npcs: BTreeMap<String, NPCState>,
+    pub dialog: Option<DialogState>,
+    #[serde(default)]
+    pub gamestate: GameState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityState {
+    pub position: (f32, f32),
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NPCState {
+    pub entity: EntityState,
+    pub behavior: BehaviorState,
+    #[serde(default)]
+    pub patrol: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BehaviorState {
+    Talking,
+    Waiting(f32),
+    Standing,
+    Wandering((f32, f32)),
+    Patrolling((f32, f32)),
+    Travelling((f32, f32)),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogState {
+    pub dialog: usize,
+    pub response: usize,
+}
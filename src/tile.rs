@@ -1,10 +1,13 @@
 use ggez::graphics::{spritebatch::SpriteBatch, DrawParam, Rect};
 use ggez::nalgebra::{Point2, Vector2};
 use std::f32::consts::PI;
+use std::str::FromStr;
 use xml::reader::XmlEvent;
 
+use crate::animations::Mode;
 use crate::constants;
-use crate::xmlelements::XMLElements;
+use crate::error::Error;
+use crate::xmlelements::{parse_value, XMLElements};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Tile {
@@ -18,12 +21,7 @@ impl Tile {
     }
 
     pub fn draw(&self, spritebatch: &mut SpriteBatch, position: Point2<f32>) {
-        let draw = match self.properties.visible {
-            Some(draw) => draw,
-            None => true,
-        };
-
-        if draw {
+        if self.properties.visible.unwrap_or(true) {
             spritebatch.add(
                 DrawParam::default()
                     .src(self.source)
@@ -49,44 +47,44 @@ pub struct Properties {
     pub keyframe: Option<usize>,
     pub delay: Option<usize>,
     pub scramble_delay: Option<bool>,
+    pub mode: Option<Mode>,
+    pub events: Option<String>,
     pub spawn: Option<String>,
+    pub name: Option<String>,
+    pub dialogtree: Option<String>,
+    pub routine: Option<String>,
     pub visible: Option<bool>,
+    pub solid: Option<bool>,
+    pub portal: Option<String>,
+    pub destination: Option<String>,
 }
 
 impl Properties {
-    pub fn new(properties_elements: Vec<XmlEvent>) -> Properties {
-        let entity = match XMLElements::get_attribute_value(&properties_elements, "entity") {
-            Ok(entity) => entity.parse().ok(),
-            Err(_) => None,
-        };
-        let keyframe = match XMLElements::get_attribute_value(&properties_elements, "keyframe") {
-            Ok(keyframe) => keyframe.parse().ok(),
-            Err(_) => None,
-        };
-        let spawn = XMLElements::get_attribute_value(&properties_elements, "spawn").ok();
-        let visible = match XMLElements::get_attribute_value(&properties_elements, "visible") {
-            Ok(visible) => visible.parse().ok(),
-            Err(_) => None,
-        };
-        let delay = match XMLElements::get_attribute_value(&properties_elements, "delay") {
-            Ok(delay) => delay.parse().ok(),
-            Err(_) => None,
-        };
-        let scramble_delay =
-            match XMLElements::get_attribute_value(&properties_elements, "scramble_delay") {
-                Ok(scramble_delay) => scramble_delay.parse().ok(),
-                Err(_) => None,
-            };
-
-        Properties {
+    pub fn new(properties_elements: Vec<XmlEvent>) -> Result<Properties, Error> {
+        Ok(Properties {
             rotation: 0.0,
-            entity,
-            keyframe,
-            delay,
-            scramble_delay,
-            spawn,
-            visible,
-        }
+            entity: parse_property(&properties_elements, "entity")?,
+            keyframe: parse_property(&properties_elements, "keyframe")?,
+            delay: parse_property(&properties_elements, "delay")?,
+            scramble_delay: parse_property(&properties_elements, "scramble_delay")?,
+            mode: parse_property(&properties_elements, "mode")?,
+            events: parse_property(&properties_elements, "events")?,
+            spawn: parse_property(&properties_elements, "spawn")?,
+            name: parse_property(&properties_elements, "name")?,
+            dialogtree: parse_property(&properties_elements, "dialogtree")?,
+            routine: parse_property(&properties_elements, "routine")?,
+            visible: parse_property(&properties_elements, "visible")?,
+            solid: parse_property(&properties_elements, "solid")?,
+            portal: parse_property(&properties_elements, "portal")?,
+            destination: parse_property(&properties_elements, "destination")?,
+        })
+    }
+}
+
+fn parse_property<T: FromStr>(elements: &[XmlEvent], name: &str) -> Result<Option<T>, Error> {
+    match XMLElements::get_attribute_value(elements, name) {
+        Some(value) => parse_value(name, &value).map(Some),
+        None => Ok(None),
     }
 }
 
@@ -98,8 +96,16 @@ impl Default for Properties {
             keyframe: None,
             delay: None,
             scramble_delay: None,
+            mode: None,
+            events: None,
             spawn: None,
+            name: None,
+            dialogtree: None,
+            routine: None,
             visible: None,
+            solid: None,
+            portal: None,
+            destination: None,
         }
     }
 }
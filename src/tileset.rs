@@ -1,33 +1,64 @@
-use ggez::filesystem::File;
-use ggez::graphics::Rect;
+use ggez::graphics::{spritebatch::SpriteBatch, Rect};
+use ggez::nalgebra::Point2;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::io::Read;
 
-use crate::animations::Animation;
+use crate::animations::{Animation, Clip, Frame, Mode, Timeline};
 use crate::constants::{self, FLIP_A, FLIP_D, FLIP_H, FLIP_V};
+use crate::error::Error;
 use crate::tile::{flip, rotate, Properties, Tile};
-use crate::xmlelements::XMLElements;
+use crate::xmlelements::{parse_value, XMLElements};
 
+#[derive(Clone)]
 pub struct Tileset {
     tiles: HashMap<usize, Tile>,
+    // tiled <animation> frames of a tile, as (tile id, duration in milliseconds)
+    animations: HashMap<usize, Vec<(usize, usize)>>,
+    // one shared clock per clip, keyed by the clip's tile id and flip flags,
+    // only built for the tiles the current map uses
+    timelines: HashMap<usize, Timeline>,
+    // the timeline each used tile plays
+    clips: HashMap<usize, usize>,
+    // the unflipped tiles of each entity, sorted by keyframe
+    keyframes: HashMap<String, Vec<usize>>,
 }
 
 impl Tileset {
-    pub fn new(file: File) -> Tileset {
-        let elements = XMLElements::new(file);
-
-        let height = elements
-            .get_element_attribute("image", "height")
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
-        let columns = elements
-            .get_element_attribute("tileset", "columns")
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
+    pub fn new<R: Read>(reader: R) -> Result<Tileset, Error> {
+        let elements = XMLElements::new(reader)?;
+
+        let height = elements.parse_element_attribute::<usize>("image", "height")?;
+        let columns = elements.parse_element_attribute::<usize>("tileset", "columns")?;
 
         let rows = height / (constants::TILE_HEIGHT as usize);
 
+        // tiles with properties or animations have a <tile> element, read them all at once
+        let mut properties = HashMap::new();
+        let mut animations = HashMap::new();
+        for index in elements.get_indices("tile") {
+            let tile_element = &elements.events[*index];
+            let id = XMLElements::parse_attribute::<usize>(tile_element, "id")? + 1;
+            properties.insert(
+                id,
+                Properties::new(elements.get_children(*index, "property"))?,
+            );
+
+            let frames = elements
+                .get_children(*index, "frame")
+                .iter()
+                .map(|frame| {
+                    Ok((
+                        XMLElements::parse_attribute::<usize>(frame, "tileid")? + 1,
+                        XMLElements::parse_attribute::<usize>(frame, "duration")?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            if !frames.is_empty() {
+                animations.insert(id, frames);
+            }
+        }
+
         let mut tiles = HashMap::new();
         tiles.insert(0, Tile::default());
 
@@ -39,22 +70,7 @@ impl Tileset {
                 let x = c as f32 / columns as f32;
                 let y = r as f32 / rows as f32;
 
-                let tile_element = elements.get_elements("tile").into_iter().find(|e| {
-                    XMLElements::get_attribute(e, "id")
-                        .unwrap()
-                        .parse::<usize>()
-                        .unwrap()
-                        + 1
-                        == id
-                });
-
-                let properties = match tile_element {
-                    Some(tile_element) => {
-                        Properties::new(elements.get_children(&tile_element, "property"))
-                    }
-                    None => Properties::default(),
-                };
-
+                let properties = properties.remove(&id).unwrap_or_default();
                 tiles.insert(id, Tile::new(Rect::new(x, y, w, h), properties));
                 id += 1;
             }
@@ -79,7 +95,94 @@ impl Tileset {
             }
         }
 
-        Tileset { tiles }
+        let mut keyframes: HashMap<String, Vec<(Option<usize>, usize)>> = HashMap::new();
+        for (id, tile) in tiles.iter() {
+            if let Some(entity) = &tile.properties.entity {
+                if id & FLIP_A == 0 {
+                    keyframes
+                        .entry(entity.clone())
+                        .or_default()
+                        .push((tile.properties.keyframe, *id));
+                }
+            }
+        }
+
+        Ok(Tileset {
+            tiles,
+            animations,
+            timelines: HashMap::new(),
+            clips: HashMap::new(),
+            keyframes: keyframes
+                .into_iter()
+                .map(|(entity, mut ids)| {
+                    ids.sort();
+                    (entity, ids.into_iter().map(|(_, id)| id).collect())
+                })
+                .collect(),
+        })
+    }
+
+    // builds the timelines of the animated tiles among the given ids, dropping the others,
+    // timelines that are still used keep their time
+    pub fn give_used_tiles(
+        &mut self,
+        tile_ids: impl IntoIterator<Item = usize>,
+    ) -> Result<(), Error> {
+        let mut timelines = HashMap::new();
+        let mut clips = HashMap::new();
+
+        for id in tile_ids {
+            let tile = self.get_tile(id)?;
+            if !self.animations.contains_key(&(id & !FLIP_A)) && tile.properties.entity.is_none() {
+                continue;
+            }
+
+            let key = self.get_clip_key(id)?;
+            if let Entry::Vacant(entry) = timelines.entry(key) {
+                entry.insert(match self.timelines.get(&key).cloned() {
+                    Some(timeline) => timeline,
+                    None => Timeline::new(self.build_clip(key & !FLIP_A, key & FLIP_A)?),
+                });
+            }
+            clips.insert(id, key);
+        }
+
+        // still tiles are drawn as they are
+        timelines.retain(|_, timeline| timeline.is_animated());
+        clips.retain(|_, key| timelines.contains_key(key));
+
+        self.timelines = timelines;
+        self.clips = clips;
+        Ok(())
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        for timeline in self.timelines.values_mut() {
+            timeline.update(delta);
+        }
+    }
+
+    pub fn draw_tile(
+        &self,
+        spritebatch: &mut SpriteBatch,
+        tile_id: usize,
+        position: Point2<f32>,
+        phase: f32,
+    ) {
+        match self.get_timeline(tile_id) {
+            Some(timeline) => timeline.draw(spritebatch, position, phase),
+            None => {
+                if let Some(tile) = self.tiles.get(&tile_id) {
+                    tile.draw(spritebatch, position);
+                }
+            }
+        }
+    }
+
+    pub fn get_timeline(&self, tile_id: usize) -> Option<&Timeline> {
+        self.clips
+            .get(&tile_id)
+            .and_then(|key| self.timelines.get(key))
     }
 
     pub fn get_spawn_tiles(&self) -> HashMap<usize, Tile> {
@@ -90,37 +193,126 @@ impl Tileset {
             .collect()
     }
 
-    pub fn get_animation(&self, tile_id: usize) -> Animation {
-        let first_tile = self
-            .tiles
+    pub fn get_portal_tiles(&self) -> HashMap<usize, Tile> {
+        self.tiles
+            .clone()
+            .into_iter()
+            .filter(|(_, t)| t.properties.portal.is_some())
+            .collect()
+    }
+
+    pub fn is_solid(&self, tile_id: usize) -> bool {
+        match self.tiles.get(&tile_id) {
+            Some(tile) => tile.properties.solid.unwrap_or(false),
+            None => false,
+        }
+    }
+
+    pub fn get_animation(&self, tile_id: usize, phase: f32) -> Result<Animation, Error> {
+        Ok(Animation::new(self.get_tile_clip(tile_id)?, phase))
+    }
+
+    fn get_tile_clip(&self, tile_id: usize) -> Result<Clip, Error> {
+        let key = self.get_clip_key(tile_id)?;
+        self.build_clip(key & !FLIP_A, key & FLIP_A)
+    }
+
+    // the id of the tile whose clip the tile plays, with the tile's flip flags
+    fn get_clip_key(&self, tile_id: usize) -> Result<usize, Error> {
+        let id = tile_id & !FLIP_A;
+        let tile = self.get_tile(tile_id)?;
+
+        // a tile of an entity plays the entity's clip, unless it has a clip of its own
+        let id = match &tile.properties.entity {
+            Some(entity) if !self.animations.contains_key(&id) => self.get_clip_id(entity)?,
+            _ => id,
+        };
+
+        Ok(id | (tile_id & FLIP_A))
+    }
+
+    pub fn get_clip(&self, entity: &str) -> Result<Clip, Error> {
+        self.build_clip(self.get_clip_id(entity)?, 0)
+    }
+
+    // prefers the entity's tile with a tiled <animation>, then its first keyframe
+    fn get_clip_id(&self, entity: &str) -> Result<usize, Error> {
+        let keyframes = self.get_keyframes(entity);
+        keyframes
             .iter()
-            .find(|(id, _)| id == &&tile_id)
-            .unwrap()
-            .1
-            .clone();
-
-        if first_tile.properties.entity.is_some() {
-            Animation::new(
-                self.tiles
-                    .values()
-                    .cloned()
-                    .filter(|t| {
-                        t.properties.entity == first_tile.properties.entity
-                            && (t.properties.rotation - first_tile.properties.rotation)
-                                < constants::FLOAT_PRECISION
-                            && t.source.x.is_sign_positive()
-                                == first_tile.source.x.is_sign_positive()
-                            && t.source.y.is_sign_positive()
-                                == first_tile.source.y.is_sign_positive()
+            .find(|id| self.animations.contains_key(id))
+            .or_else(|| keyframes.first())
+            .cloned()
+            .ok_or_else(|| Error::MissingEntity(entity.to_string()))
+    }
+
+    fn get_keyframes(&self, entity: &str) -> &[usize] {
+        self.keyframes
+            .get(entity)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // the frames of flipped or rotated tiles are flipped or rotated alike
+    fn build_clip(&self, id: usize, flags: usize) -> Result<Clip, Error> {
+        let tile = self.get_tile(id | flags)?;
+
+        let mut frames = match (self.animations.get(&id), &tile.properties.entity) {
+            (Some(frames), _) => frames
+                .iter()
+                .map(|(frame, duration)| {
+                    Ok(Frame {
+                        tile: self.get_tile(frame | flags)?.clone(),
+                        duration: *duration as f32 / 1000.0,
+                        event: None,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+            // entities without an <animation> loop through their keyframes
+            (None, Some(entity)) => self
+                .get_keyframes(entity)
+                .iter()
+                .map(|keyframe| {
+                    let tile = self.get_tile(keyframe | flags)?.clone();
+                    Ok(Frame {
+                        duration: tile.properties.delay.unwrap_or(0) as f32 / 1000.0,
+                        tile,
+                        event: None,
                     })
-                    .collect(),
-            )
-        } else {
-            Animation::new(vec![first_tile])
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+            (None, None) => vec![Frame {
+                tile: tile.clone(),
+                duration: 0.0,
+                event: None,
+            }],
+        };
+
+        // events are listed as frame:name pairs, e.g. "1:step,3:step"
+        for event in tile.properties.events.iter().flat_map(|e| e.split(',')) {
+            let bad_value = || Error::BadValue("events".to_string(), event.to_string());
+            let mut parts = event.splitn(2, ':');
+            let frame: usize = parse_value("events", parts.next().unwrap_or("").trim())?;
+            let name = parts.next().ok_or_else(bad_value)?.trim();
+            frames.get_mut(frame).ok_or_else(bad_value)?.event = Some(name.to_string());
         }
+
+        Ok(Clip {
+            frames,
+            mode: tile.properties.mode.unwrap_or(Mode::Loop),
+            scramble: tile.properties.scramble_delay.unwrap_or(false),
+        })
     }
 
-    pub fn get_tile_by_entity_keyframe(&self, entity: &str, keyframe: usize) -> Tile {
+    pub fn get_tile(&self, tile_id: usize) -> Result<&Tile, Error> {
+        self.tiles.get(&tile_id).ok_or(Error::MissingTile(tile_id))
+    }
+
+    pub fn get_tile_by_entity_keyframe(
+        &self,
+        entity: &str,
+        keyframe: usize,
+    ) -> Result<Tile, Error> {
         self.tiles
             .values()
             .find(|t| {
@@ -130,7 +322,7 @@ impl Tileset {
                     && t.source.x > 0.0
                     && t.source.y > 0.0
             })
-            .unwrap()
-            .clone()
+            .cloned()
+            .ok_or_else(|| Error::MissingEntity(entity.to_string()))
     }
 }
@@ -1,57 +1,281 @@
-use ggez::event::KeyCode;
-use ggez::graphics::spritebatch::SpriteBatch;
-use ggez::nalgebra::distance;
-use ggez::{filesystem, Context};
+use ggez::filesystem;
+use ggez::graphics::{spritebatch::SpriteBatch, Rect};
+use ggez::nalgebra::{distance, Point2};
+use ggez::Context;
+use std::collections::HashMap;
+use std::fs;
 
+use crate::bindings::Command;
+use crate::character::Character;
+use crate::clock::Clock;
 use crate::constants;
-use crate::dialogbox::DialogTree;
+use crate::dialogbox::{DialogBox, DialogTree};
 use crate::entity::Operable;
-use crate::map::Map;
-use crate::npc::{Character, NPC};
+use crate::error::Error;
+use crate::gamestate::GameState;
+use crate::map::{Map, Portal};
+use crate::npc::NPC;
 use crate::player::Player;
+use crate::resources::Resources;
+use crate::save::{DialogState, Save};
 use crate::tileset::Tileset;
 
 #[derive(Clone)]
 pub struct World {
     map: Map,
     pub player: Player,
+    pub gamestate: GameState,
     npcs: Vec<NPC>,
+    tileset: Tileset,
+    characters: HashMap<String, Character>,
+    maps: HashMap<String, String>,
+    current_map: String,
+    portal: Option<Portal>,
 }
 
 impl Operable for World {
-    fn update(&mut self) {
-        self.map.update();
-        self.player.update();
+    fn update(&mut self, delta: f32) {
+        self.gamestate.clock.update(delta);
+        self.tileset.update(delta);
+        self.player.update(delta);
         for npc in self.npcs.iter_mut() {
-            npc.update();
+            npc.give_hour(self.gamestate.clock.get_hour());
+            npc.update(delta);
         }
     }
 
     fn draw(&self, spritebatch: &mut SpriteBatch) {
-        self.map.draw(spritebatch);
-        self.player.draw(spritebatch);
-        for npc in self.npcs.iter() {
-            npc.draw(spritebatch);
+        self.map.draw_below(spritebatch, &self.tileset);
+        for (_, sprite) in self.get_sprites() {
+            sprite.draw(spritebatch);
         }
+        self.map.draw_above(spritebatch, &self.tileset);
     }
 }
 
 impl World {
-    pub fn new(context: &mut Context) -> World {
-        let tileset = Tileset::new(filesystem::open(context, "/tileset.tsx").unwrap());
-        let map = Map::new(filesystem::open(context, "/map.tmx").unwrap(), &tileset);
+    pub fn new(resources: &mut dyn Resources) -> Result<World, Error> {
+        let mut tileset =
+            Tileset::new(resources.open("/tileset.tsx")?).map_err(|e| e.in_file("/tileset.tsx"))?;
+        let maps: HashMap<String, String> = serde_json::from_reader(resources.open("/maps")?)
+            .map_err(|e| Error::BadJson("/maps".to_string(), e))?;
+        let characters = Character::load_all(resources)?;
+
+        let (map, spawn) = World::load_map(resources, &tileset, &maps, constants::START_MAP, None)?;
+        tileset
+            .give_used_tiles(map.get_tile_ids())
+            .map_err(|e| e.in_file("/tileset.tsx"))?;
 
-        World {
-            map: map.clone(),
-            player: Player::new(
+        let player = Player::new(
+            Character::get(&characters, constants::PLAYER)?,
+            &tileset,
+            spawn,
+            map.get_collision(),
+        )?;
+
+        let gamestate = GameState::new();
+        Ok(World {
+            npcs: NPC::build_npcs(
+                resources,
                 &tileset,
-                map.get_spawn_points(Character::Player)[0],
-                map.get_dimensions(),
-            ),
-            npcs: NPC::build_npcs(context, &tileset, &map),
+                &characters,
+                &map,
+                gamestate.clock.get_hour(),
+            )?,
+            portal: map.get_portal(player.entity.get_feet()),
+            player,
+            gamestate,
+            map,
+            tileset,
+            characters,
+            maps,
+            current_map: constants::START_MAP.to_string(),
+        })
+    }
+
+    fn load_map(
+        resources: &mut dyn Resources,
+        tileset: &Tileset,
+        maps: &HashMap<String, String>,
+        name: &str,
+        destination: Option<&str>,
+    ) -> Result<(Map, Point2<f32>), Error> {
+        let path = maps
+            .get(name)
+            .ok_or_else(|| Error::UnknownMap(name.to_string()))?;
+        let map = Map::new(resources.open(path)?, tileset).map_err(|e| e.in_file(path))?;
+
+        let spawn = destination
+            .and_then(|destination| map.get_named_point(destination))
+            .or_else(|| map.get_spawn_points(constants::PLAYER).first().cloned())
+            .ok_or_else(|| Error::MissingSpawn(constants::PLAYER.to_string()).in_file(path))?;
+
+        Ok((map, spawn))
+    }
+
+    pub fn travel(&mut self, resources: &mut dyn Resources, portal: &Portal) -> Result<(), Error> {
+        let (map, spawn) = World::load_map(
+            resources,
+            &self.tileset,
+            &self.maps,
+            &portal.map,
+            portal.destination.as_deref(),
+        )?;
+        // nothing changes until the whole map loaded, a failed travel keeps the current one
+        let mut tileset = self.tileset.clone();
+        tileset
+            .give_used_tiles(map.get_tile_ids())
+            .map_err(|e| e.in_file("/tileset.tsx"))?;
+        let npcs = NPC::build_npcs(
+            resources,
+            &tileset,
+            &self.characters,
+            &map,
+            self.gamestate.clock.get_hour(),
+        )?;
+
+        self.tileset = tileset;
+        self.npcs = npcs;
+        self.player.entity.teleport(spawn, map.get_collision());
+        self.map = map;
+        self.current_map = portal.map.clone();
+        self.portal = self.map.get_portal(self.player.entity.get_feet());
+
+        Ok(())
+    }
+
+    pub fn save(&self, context: &mut Context, dialogbox: &DialogBox) -> Result<(), Error> {
+        let save = self.get_save(dialogbox.save());
+
+        let directory = filesystem::user_data_dir(context);
+        let path = directory.join(constants::SAVE_FILE);
+        let display_path = path.display().to_string();
+
+        fs::create_dir_all(directory).map_err(|e| Error::Io(display_path.clone(), e))?;
+        let file = fs::File::create(&path).map_err(|e| Error::Io(display_path.clone(), e))?;
+        serde_json::to_writer_pretty(file, &save).map_err(|e| Error::BadJson(display_path, e))
+    }
+
+    pub fn load(&mut self, context: &mut Context, dialogbox: &mut DialogBox) -> Result<(), Error> {
+        let path = filesystem::user_data_dir(context).join(constants::SAVE_FILE);
+        let display_path = path.display().to_string();
+
+        let file = fs::File::open(&path).map_err(|e| Error::Io(display_path.clone(), e))?;
+        let save: Save =
+            serde_json::from_reader(file).map_err(|e| Error::BadJson(display_path, e))?;
+
+        let dialogtree = self.give_save(context, &save)?;
+        dialogbox.load(dialogtree, save.dialog.as_ref(), &self.gamestate);
+
+        Ok(())
+    }
+
+    pub fn get_save(&self, dialog: Option<DialogState>) -> Save {
+        Save {
+            map: self.current_map.clone(),
+            player: self.player.entity.save(),
+            npcs: self
+                .npcs
+                .iter()
+                .map(|npc| (npc.get_id().to_string(), npc.save()))
+                .collect(),
+            dialog,
+            gamestate: self.gamestate.clone(),
         }
     }
 
+    // the world is left as it was if the save doesn't match the map's npcs,
+    // returns the dialog tree of the npc the player was talking to
+    pub fn give_save(
+        &mut self,
+        resources: &mut dyn Resources,
+        save: &Save,
+    ) -> Result<Option<DialogTree>, Error> {
+        let mut world = self.clone();
+        world.gamestate = save.gamestate.clone();
+        world.travel(
+            resources,
+            &Portal {
+                map: save.map.clone(),
+                destination: None,
+            },
+        )?;
+
+        if let Some(id) = save
+            .npcs
+            .keys()
+            .find(|id| !world.npcs.iter().any(|npc| npc.get_id() == id.as_str()))
+        {
+            return Err(Error::UnknownSaveState(id.clone()));
+        }
+
+        world.player.entity.load(&save.player);
+        for npc in world.npcs.iter_mut() {
+            let state = save
+                .npcs
+                .get(npc.get_id())
+                .ok_or_else(|| Error::MissingSaveState(npc.get_id().to_string()))?;
+            npc.load(state);
+        }
+        world.portal = world.map.get_portal(world.player.entity.get_feet());
+
+        let dialogtree = world
+            .npcs
+            .iter_mut()
+            .find(|npc| npc.is_talking())
+            .map(|npc| npc.get_dialogtree());
+
+        *self = world;
+        Ok(dialogtree)
+    }
+
+    pub fn get_entered_portal(&mut self) -> Option<Portal> {
+        let portal = self.map.get_portal(self.player.entity.get_feet());
+        let entered = if portal != self.portal {
+            portal.clone()
+        } else {
+            None
+        };
+        self.portal = portal;
+        entered
+    }
+
+    pub fn get_npcs(&self) -> &[NPC] {
+        &self.npcs
+    }
+
+    pub fn get_current_map(&self) -> &str {
+        &self.current_map
+    }
+
+    pub fn give_view(&mut self, view: Rect) {
+        self.map.give_view(view);
+    }
+
+    pub fn get_clock(&self) -> &Clock {
+        &self.gamestate.clock
+    }
+
+    // the positions of the player and npcs, in the order they are drawn
+    pub fn get_draw_order(&self) -> Vec<Point2<f32>> {
+        self.get_sprites()
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    // entities further down the screen are drawn over the ones behind them
+    fn get_sprites(&self) -> Vec<(Point2<f32>, &dyn Operable)> {
+        let mut sprites: Vec<(Point2<f32>, &dyn Operable)> =
+            vec![(self.player.get_position(), &self.player)];
+        for npc in self.npcs.iter() {
+            sprites.push((npc.entity.position, npc));
+        }
+
+        sprites.sort_by(|a, b| a.0.y.total_cmp(&b.0.y));
+        sprites
+    }
+
     pub fn player_in_talking_range(&mut self) -> bool {
         if let Some(npc) = self.npcs.iter_mut().find(|npc| npc.is_talking()) {
             if constants::INTERACT_DISTANCE
@@ -67,23 +291,28 @@ impl World {
         }
     }
 
+    pub fn stop_talking(&mut self) {
+        for npc in self.npcs.iter_mut().filter(|npc| npc.is_talking()) {
+            npc.stop_talking();
+        }
+    }
+
     pub fn get_dialogtree(&mut self) -> Option<DialogTree> {
         let player_position = self.player.entity.position;
-        if let Some(npc) = self.npcs.iter_mut().find(|npc| {
-            constants::INTERACT_DISTANCE > distance(&player_position, &npc.entity.position)
-        }) {
-            Some(npc.get_dialogtree())
-        } else {
-            None
-        }
+        self.npcs
+            .iter_mut()
+            .find(|npc| {
+                constants::INTERACT_DISTANCE > distance(&player_position, &npc.entity.position)
+            })
+            .map(|npc| npc.get_dialogtree())
     }
 
-    pub fn give_key_up(&mut self, keycode: KeyCode) {
-        self.player.give_key_up(keycode);
+    pub fn give_command_up(&mut self, command: Command) {
+        self.player.give_command_up(command);
     }
 
-    pub fn give_key_down(&mut self, keycode: KeyCode) {
-        self.player.give_key_down(keycode);
+    pub fn give_command_down(&mut self, command: Command) {
+        self.player.give_command_down(command);
     }
 
     pub fn get_dimensions(&self) -> (f32, f32) {
@@ -1,129 +1,150 @@
-use ggez::filesystem::File;
-use std::io::BufReader;
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+use std::str::FromStr;
 use xml::reader::{
     EventReader,
     XmlEvent::{self, EndElement, StartElement},
 };
 
+use crate::error::Error;
+
 pub struct XMLElements {
     pub events: Vec<XmlEvent>,
+    // the index of the event closing each element, other events close themselves
+    ends: Vec<usize>,
+    // the indices of the elements of each name, in document order
+    names: HashMap<String, Vec<usize>>,
 }
 
 impl XMLElements {
-    pub fn new(file: File) -> XMLElements {
-        XMLElements {
-            events: EventReader::new(BufReader::new(file))
-                .into_iter()
-                .map(Result::unwrap)
-                .collect(),
-        }
-    }
-
-    pub fn get_element(&self, element_name: &str) -> XmlEvent {
-        self.events
-            .clone()
+    pub fn new<R: Read>(reader: R) -> Result<XMLElements, Error> {
+        let events: Vec<XmlEvent> = EventReader::new(BufReader::new(reader))
             .into_iter()
-            .find(|e| {
-                if let StartElement { name, .. } = e {
-                    name.local_name == element_name
-                } else {
-                    false
-                }
-            })
-            .unwrap()
-    }
+            .collect::<Result<_, _>>()?;
 
-    pub fn get_children(&self, element: &XmlEvent, children_name: &str) -> Vec<XmlEvent> {
-        let start_index = self.events.iter().position(|e| e == element).unwrap();
+        let mut ends: Vec<usize> = (0..events.len()).collect();
+        let mut names: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut open = Vec::new();
+        for (i, event) in events.iter().enumerate() {
+            match event {
+                StartElement { name, .. } => {
+                    open.push(i);
+                    names.entry(name.local_name.clone()).or_default().push(i);
+                }
+                EndElement { .. } => {
+                    if let Some(start) = open.pop() {
+                        ends[start] = i;
+                    }
+                }
+                _ => (),
+            }
+        }
 
-        let element_name = if let StartElement { name, .. } = element {
-            &name.local_name
-        } else {
-            ""
-        };
+        Ok(XMLElements {
+            events,
+            ends,
+            names,
+        })
+    }
 
-        let end_index = self.events[start_index..]
-            .iter()
-            .position(|e| {
-                if let EndElement { name } = e {
-                    element_name == name.local_name
-                } else {
-                    false
-                }
-            })
-            .unwrap()
-            + start_index;
+    pub fn get_element(&self, element_name: &str) -> Result<XmlEvent, Error> {
+        self.get_indices(element_name)
+            .first()
+            .map(|i| self.events[*i].clone())
+            .ok_or_else(|| Error::MissingElement(element_name.to_string()))
+    }
 
-        self.events[start_index..end_index]
+    // the elements inside the element at the given index
+    pub fn get_children(&self, index: usize, children_name: &str) -> Vec<XmlEvent> {
+        let end = self.ends.get(index).cloned().unwrap_or(index);
+        let indices = self.get_indices(children_name);
+        let first = indices.partition_point(|i| *i <= index);
+        let last = indices.partition_point(|i| *i < end);
+        indices[first..last]
             .iter()
-            .cloned()
-            .filter(|e| {
-                if let StartElement { name, .. } = e {
-                    name.local_name == children_name
-                } else {
-                    false
-                }
-            })
+            .map(|i| self.events[*i].clone())
             .collect()
     }
 
     pub fn get_elements(&self, element_name: &str) -> Vec<XmlEvent> {
-        self.events
-            .clone()
-            .into_iter()
-            .filter(|e| {
-                if let StartElement { name, .. } = e {
-                    name.local_name == element_name
-                } else {
-                    false
-                }
-            })
+        self.get_indices(element_name)
+            .iter()
+            .map(|i| self.events[*i].clone())
             .collect()
     }
 
+    pub fn get_indices(&self, element_name: &str) -> &[usize] {
+        self.names
+            .get(element_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
     pub fn get_element_attribute(
         &self,
         element_name: &str,
         attribute_name: &str,
-    ) -> Result<String, ()> {
-        let element = self.get_element(element_name);
-        XMLElements::get_attribute(&element, attribute_name)
+    ) -> Result<String, Error> {
+        let element = self.get_element(element_name)?;
+        XMLElements::require_attribute(&element, attribute_name)
+    }
+
+    pub fn parse_element_attribute<T: FromStr>(
+        &self,
+        element_name: &str,
+        attribute_name: &str,
+    ) -> Result<T, Error> {
+        let value = self.get_element_attribute(element_name, attribute_name)?;
+        parse_value(&format!("{}.{}", element_name, attribute_name), &value)
     }
 
-    pub fn get_attribute(element: &XmlEvent, attribute_name: &str) -> Result<String, ()> {
+    pub fn get_attribute(element: &XmlEvent, attribute_name: &str) -> Option<String> {
         if let StartElement { attributes, .. } = element {
-            Ok(attributes
+            attributes
                 .iter()
                 .find(|a| a.name.local_name == attribute_name)
-                .unwrap()
-                .value
-                .clone())
+                .map(|a| a.value.clone())
         } else {
-            Err(())
+            None
         }
     }
 
-    pub fn get_attribute_value(elements: &[XmlEvent], attribute_name: &str) -> Result<String, ()> {
-        let element = elements
-            .iter()
-            .find(|e| {
-                if let StartElement { attributes, .. } = e {
-                    attributes.iter().any(|a| a.value == attribute_name)
-                } else {
-                    false
-                }
-            })
-            .ok_or(())?;
+    pub fn require_attribute(element: &XmlEvent, attribute_name: &str) -> Result<String, Error> {
+        XMLElements::get_attribute(element, attribute_name).ok_or_else(|| {
+            let element_name = if let StartElement { name, .. } = element {
+                name.local_name.clone()
+            } else {
+                String::new()
+            };
+            Error::MissingAttribute(element_name, attribute_name.to_string())
+        })
+    }
 
-        if let StartElement { attributes, .. } = element {
-            Ok(attributes
-                .iter()
-                .find(|a| a.name.local_name == "value")
-                .unwrap()
-                .value
-                .clone())
-        } else {
-            Err(())
-        }
+    pub fn parse_attribute<T: FromStr>(
+        element: &XmlEvent,
+        attribute_name: &str,
+    ) -> Result<T, Error> {
+        parse_value(
+            attribute_name,
+            &XMLElements::require_attribute(element, attribute_name)?,
+        )
     }
+
+    pub fn get_attribute_value(elements: &[XmlEvent], attribute_name: &str) -> Option<String> {
+        let element = elements.iter().find(|e| {
+            if let StartElement { attributes, .. } = e {
+                attributes.iter().any(|a| a.value == attribute_name)
+            } else {
+                false
+            }
+        })?;
+
+        XMLElements::get_attribute(element, "value")
+    }
+}
+
+pub fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::BadValue(name.to_string(), value.to_string()))
 }
@@ -0,0 +1,295 @@
+use pax_romana::animations::{Animation, Animations, Timeline};
+use pax_romana::character::Character;
+use pax_romana::constants;
+use pax_romana::entity::{Action, Direction};
+use pax_romana::error::Error;
+use pax_romana::resources::Directory;
+use pax_romana::tileset::Tileset;
+
+mod common;
+use common::{tileset, RESOURCES};
+
+fn entity(id: usize, entity: &str, keyframe: usize) -> String {
+    format!(
+        r#"<tile id="{}"><properties><property name="delay" type="int" value="100"/><property name="entity" value="{}"/><property name="keyframe" type="int" value="{}"/></properties></tile>"#,
+        id, entity, keyframe
+    )
+}
+
+// a 4x4 tileset with a knight walking left, up and down
+fn knight() -> Tileset {
+    let tiles = [
+        entity(5, "knight-left", 0),
+        entity(6, "knight-left", 1),
+        entity(7, "knight-up", 0),
+        entity(9, "knight-down", 0),
+        entity(10, "knight-down", 1),
+        entity(11, "knight-down", 2),
+    ]
+    .concat();
+    let tileset = format!(
+        r#"<tileset tilewidth="16" tileheight="16" tilecount="16" columns="4"><image source="tileset.png" width="64" height="64"/>{}</tileset>"#,
+        tiles
+    );
+
+    Tileset::new(tileset.as_bytes()).unwrap()
+}
+
+fn step(animations: &mut Animations, action: &Action, seconds: f32) {
+    for _ in 0..(seconds * 60.0) as usize {
+        animations.update(action, 1.0 / 60.0);
+    }
+}
+
+#[test]
+fn each_direction_uses_its_own_frames() {
+    let tileset = knight();
+    let mut animations = Animations::new(&tileset, "knight").unwrap();
+    let up = tileset.get_tile_by_entity_keyframe("knight-up", 0).unwrap();
+    let down = tileset
+        .get_tile_by_entity_keyframe("knight-down", 0)
+        .unwrap();
+    let left = tileset
+        .get_tile_by_entity_keyframe("knight-left", 0)
+        .unwrap();
+
+    step(&mut animations, &Action::Idle(Direction::Up), 0.1);
+    assert_eq!(animations.get_tile().source.x, up.source.x);
+
+    step(&mut animations, &Action::Idle(Direction::Down), 0.1);
+    assert_eq!(animations.get_tile().source.x, down.source.x);
+
+    step(&mut animations, &Action::Idle(Direction::Left), 0.1);
+    assert_eq!(animations.get_tile().source.x, left.source.x);
+
+    // the right side mirrors the left
+    step(&mut animations, &Action::Idle(Direction::Right), 0.1);
+    assert_eq!(
+        animations.get_tile().source.x,
+        -left.source.x - left.source.w
+    );
+}
+
+#[test]
+fn walking_cycles_through_every_keyframe() {
+    let tileset = knight();
+    let mut animations = Animations::new(&tileset, "knight").unwrap();
+
+    let mut seen = Vec::new();
+    for _ in 0..60 {
+        step(&mut animations, &Action::MovingDown, 0.05);
+        let x = animations.get_tile().source.x;
+        if !seen.contains(&x) {
+            seen.push(x);
+        }
+    }
+    assert_eq!(seen.len(), 3);
+
+    step(&mut animations, &Action::MovingUp, 0.5);
+    let up = tileset.get_tile_by_entity_keyframe("knight-up", 0).unwrap();
+    assert_eq!(animations.get_tile().source.x, up.source.x);
+}
+
+#[test]
+fn missing_sides_are_an_error() {
+    let tileset = tileset();
+
+    assert!(Animations::new(&tileset, "player").is_ok());
+    assert!(Animations::new(&tileset, "gladiator").is_err());
+}
+
+#[test]
+fn broken_sides_are_an_error() {
+    let tileset = format!(
+        r#"<tileset tilewidth="16" tileheight="16" tilecount="16" columns="4"><image source="tileset.png" width="64" height="64"/>{}{}</tileset>"#,
+        entity(5, "knight-right", 0),
+        r#"<tile id="6"><properties><property name="entity" value="knight-left"/><property name="events" value="3:step"/></properties></tile>"#
+    );
+    let tileset = Tileset::new(tileset.as_bytes()).unwrap();
+
+    // the left side isn't mirrored from the right, its bad event is reported
+    match Animations::new(&tileset, "knight") {
+        Err(Error::BadValue(property, _)) => assert_eq!(property, "events"),
+        _ => panic!("a broken side should be an error"),
+    }
+}
+
+#[test]
+fn characters_have_their_own_sprites() {
+    let tileset = tileset();
+    let characters = Character::load_all(&mut Directory::new(RESOURCES)).unwrap();
+
+    let mut sources = Vec::new();
+    for name in ["player", "peasant", "soldier"].iter() {
+        let character = Character::get(&characters, name).unwrap();
+        let animations = Animations::new(&tileset, character.get_entity()).unwrap();
+        let source = animations.get_tile().source;
+        assert!(!sources.contains(&source), "{} shares a sprite", name);
+        sources.push(source);
+    }
+}
+
+#[test]
+fn characters_face_every_direction() {
+    let tileset = tileset();
+
+    for name in ["player", "peasant", "soldier"].iter() {
+        let mut animations = Animations::new(&tileset, name).unwrap();
+        let mut sources = Vec::new();
+        for action in [Action::MovingLeft, Action::MovingUp, Action::MovingDown].iter() {
+            animations.update(action, 0.0);
+            let source = animations.get_tile().source;
+            assert!(
+                !sources.contains(&source),
+                "{} {:?} has no sprite",
+                name,
+                action
+            );
+            sources.push(source);
+        }
+    }
+}
+
+// a 4x4 tileset with clips defined through tiled's <animation> element
+fn clips() -> Tileset {
+    let tileset = r#"<tileset tilewidth="16" tileheight="16" tilecount="16" columns="4">
+ <image source="tileset.png" width="64" height="64"/>
+ <tile id="5">
+  <properties>
+   <property name="entity" value="attack"/>
+   <property name="mode" value="once"/>
+   <property name="events" value="0:swing,2:hit"/>
+  </properties>
+  <animation>
+   <frame tileid="5" duration="100"/>
+   <frame tileid="6" duration="50"/>
+   <frame tileid="7" duration="200"/>
+  </animation>
+ </tile>
+ <tile id="9">
+  <properties>
+   <property name="entity" value="torch"/>
+   <property name="mode" value="ping_pong"/>
+  </properties>
+  <animation>
+   <frame tileid="9" duration="100"/>
+   <frame tileid="10" duration="100"/>
+   <frame tileid="11" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="10">
+  <properties>
+   <property name="entity" value="torch"/>
+  </properties>
+ </tile>
+</tileset>"#;
+
+    Tileset::new(tileset.as_bytes()).unwrap()
+}
+
+// steps in whole milliseconds, collecting the frame shown and the events fired each step
+fn play(animation: &mut Animation, milliseconds: usize) -> (Vec<usize>, Vec<String>) {
+    let mut frames = Vec::new();
+    let mut events = Vec::new();
+    for _ in 0..milliseconds {
+        animation.update(0.001);
+        frames.push(animation.get_frame());
+        events.extend(animation.get_events().iter().cloned());
+    }
+    (frames, events)
+}
+
+#[test]
+fn one_shot_clips_finish_on_their_last_frame() {
+    let mut attack = Animation::new(clips().get_clip("attack").unwrap(), 0.0);
+    assert!(!attack.is_finished());
+
+    // steps are a millisecond long, give or take rounding
+    let (frames, events) = play(&mut attack, 340);
+    let shown = |frame: usize| frames.iter().filter(|shown| **shown == frame).count() as isize;
+    assert!((shown(0) - 100).abs() <= 1);
+    assert!((shown(1) - 50).abs() <= 1);
+    assert!((shown(2) - 190).abs() <= 1);
+    assert_eq!(events, vec!["swing", "hit"]);
+    assert!(!attack.is_finished());
+
+    let (frames, events) = play(&mut attack, 20);
+    assert!(attack.is_finished());
+    assert!(frames.iter().all(|frame| *frame == 2));
+    assert!(events.is_empty());
+}
+
+#[test]
+fn ping_pong_clips_play_back_and_forth() {
+    let mut torch = Animation::new(clips().get_clip("torch").unwrap(), 0.0);
+
+    let mut frames = Vec::new();
+    for _ in 0..6 {
+        frames.push(torch.get_frame());
+        torch.update(0.1);
+    }
+    assert_eq!(frames, vec![0, 1, 2, 1, 0, 1]);
+    assert!(!torch.is_finished());
+}
+
+#[test]
+fn timelines_play_clips_by_time() {
+    let clips = clips();
+
+    // halfway through each frame, so rounding doesn't matter
+    let mut torch = Timeline::new(clips.get_clip("torch").unwrap());
+    let mut frames = Vec::new();
+    torch.update(0.05);
+    for _ in 0..6 {
+        frames.push(torch.get_frame(0.0));
+        torch.update(0.1);
+    }
+    assert_eq!(frames, vec![0, 1, 2, 1, 0, 1]);
+
+    let attack = clips.get_clip("attack").unwrap();
+    assert_eq!(attack.get_frame_at(0.12), 1);
+    assert_eq!(attack.get_frame_at(10.0), 2);
+}
+
+#[test]
+fn flipped_cells_play_flipped_frames() {
+    let tileset = clips();
+
+    // tile 10 is part of the torch, so a cell showing it plays the whole clip
+    let mut torch = tileset.get_animation(11 | constants::FLIP_H, 0.0).unwrap();
+    let first = torch.get_tile().source;
+    torch.update(0.1);
+    let second = torch.get_tile().source;
+
+    assert!(first.x < 0.0 && second.x < 0.0);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn tileset_flames_flicker() {
+    let tileset = tileset();
+    let flame = tileset.get_clip("flame").unwrap();
+    assert_eq!(flame.frames.len(), 2);
+    assert!(flame.scramble);
+
+    let mut animation = Animation::new(flame.clone(), 0.25);
+    let (frames, _) = play(&mut animation, 1000);
+    assert!(frames.contains(&0) && frames.contains(&1));
+
+    // the same phase always flickers the same way, another phase doesn't
+    let (same, _) = play(&mut Animation::new(flame.clone(), 0.25), 1000);
+    let (other, _) = play(&mut Animation::new(flame, 0.75), 1000);
+    assert_eq!(frames, same);
+    assert_ne!(frames, other);
+}
+
+#[test]
+fn time_left_over_from_a_frame_carries_on() {
+    let mut attack = Animation::new(clips().get_clip("attack").unwrap(), 0.0);
+
+    // 20ms into the second frame, which lasts 50ms
+    attack.update(0.12);
+    assert_eq!(attack.get_frame(), 1);
+    attack.update(0.04);
+    assert_eq!(attack.get_frame(), 2);
+}
@@ -0,0 +1,182 @@
+use ggez::event::{Axis, Button, KeyCode};
+
+use pax_romana::bindings::{Bindings, Command, Stick};
+use pax_romana::entity::{Action, Direction};
+use pax_romana::resources::Directory;
+use pax_romana::world::World;
+
+mod common;
+use common::{world, RESOURCES};
+
+fn bindings() -> Bindings {
+    Bindings::new(&mut Directory::new(RESOURCES)).unwrap()
+}
+
+#[test]
+fn arrow_keys_move_by_default() {
+    let bindings = bindings();
+
+    assert_eq!(bindings.get_command(KeyCode::Up), Some(Command::MoveUp));
+    assert_eq!(bindings.get_command(KeyCode::Down), Some(Command::MoveDown));
+    assert_eq!(bindings.get_command(KeyCode::Left), Some(Command::MoveLeft));
+    assert_eq!(
+        bindings.get_command(KeyCode::Right),
+        Some(Command::MoveRight)
+    );
+    assert_eq!(bindings.get_command(KeyCode::W), Some(Command::MoveUp));
+    assert_eq!(bindings.get_command(KeyCode::E), Some(Command::Interact));
+    assert_eq!(bindings.get_command(KeyCode::Z), None);
+}
+
+#[test]
+fn overrides_rebind_only_the_listed_commands() {
+    let mut bindings = bindings();
+
+    bindings
+        .give_overrides(r#"{"keys": {"move_up": ["Z"], "interact": ["W"]}}"#.as_bytes())
+        .unwrap();
+
+    assert_eq!(bindings.get_command(KeyCode::Z), Some(Command::MoveUp));
+    assert_eq!(bindings.get_command(KeyCode::Up), None);
+    assert_eq!(bindings.get_command(KeyCode::W), Some(Command::Interact));
+    assert_eq!(bindings.get_command(KeyCode::E), None);
+    assert_eq!(bindings.get_keys(Command::MoveLeft).len(), 2);
+    assert_eq!(
+        bindings.get_button_command(Button::DPadUp),
+        Some(Command::MoveUp)
+    );
+
+    assert!(bindings
+        .give_overrides(r#"{"keys": {"fly": ["F"]}}"#.as_bytes())
+        .is_err());
+    assert!(bindings
+        .give_overrides(r#"{"keys": {"move_up": ["NotAKey"]}}"#.as_bytes())
+        .is_err());
+}
+
+#[test]
+fn key_only_files_still_rebind_keys() {
+    let mut bindings = bindings();
+
+    bindings
+        .give_overrides(r#"{"move_up": ["Z"]}"#.as_bytes())
+        .unwrap();
+    assert_eq!(bindings.get_command(KeyCode::Z), Some(Command::MoveUp));
+    assert_eq!(bindings.get_command(KeyCode::W), None);
+    assert_eq!(
+        bindings.get_button_command(Button::DPadUp),
+        Some(Command::MoveUp)
+    );
+
+    assert!(bindings
+        .give_overrides(r#"{"keys": {"move_up": ["Z"]}, "mouse": {}}"#.as_bytes())
+        .is_err());
+}
+
+#[test]
+fn bad_entries_are_named_in_the_error() {
+    let mut bindings = bindings();
+
+    for (json, entry) in &[
+        (r#"{"keys": {"fly": ["F"]}}"#, "fly"),
+        (r#"{"buttons": {"move_up": ["Trigger"]}}"#, "Trigger"),
+        (r#"{"move_up": ["NotAKey"]}"#, "NotAKey"),
+    ] {
+        let error = bindings.give_overrides(json.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains(entry), "{}", error);
+    }
+}
+
+#[test]
+fn commands_move_the_player() {
+    let bindings = bindings();
+    let mut world = world();
+
+    let command = bindings.get_command(KeyCode::Left).unwrap();
+    world.give_command_down(command);
+    assert_eq!(world.player.entity.action, Action::MovingLeft);
+
+    world.give_command_down(bindings.get_command(KeyCode::Up).unwrap());
+    assert_eq!(world.player.entity.action, Action::MovingUpLeft);
+}
+
+#[test]
+fn gamepad_buttons_navigate_dialogs() {
+    let bindings = bindings();
+
+    assert_eq!(
+        bindings.get_button_command(Button::South),
+        Some(Command::ChooseResponse)
+    );
+    assert_eq!(
+        bindings.get_button_command(Button::RightTrigger),
+        Some(Command::NextResponse)
+    );
+    assert_eq!(
+        bindings.get_button_command(Button::LeftTrigger),
+        Some(Command::PrevResponse)
+    );
+    assert_eq!(
+        bindings.get_button_command(Button::DPadLeft),
+        Some(Command::MoveLeft)
+    );
+}
+
+#[test]
+fn stick_maps_to_eight_directions() {
+    let direction = |x: f32, y: f32| {
+        let mut stick = Stick::new();
+        stick.give_axis(Axis::LeftStickX, x);
+        stick.give_axis(Axis::LeftStickY, y);
+        stick.get_commands()
+    };
+
+    assert_eq!(direction(0.1, -0.1), vec![]);
+    assert_eq!(direction(1.0, 0.1), vec![Command::MoveRight]);
+    assert_eq!(
+        direction(0.7, 0.7),
+        vec![Command::MoveUp, Command::MoveRight]
+    );
+    assert_eq!(direction(-0.1, 0.9), vec![Command::MoveUp]);
+    assert_eq!(
+        direction(-0.6, 0.7),
+        vec![Command::MoveUp, Command::MoveLeft]
+    );
+    assert_eq!(direction(-1.0, -0.1), vec![Command::MoveLeft]);
+    assert_eq!(
+        direction(-0.7, -0.7),
+        vec![Command::MoveDown, Command::MoveLeft]
+    );
+    assert_eq!(direction(0.0, -1.0), vec![Command::MoveDown]);
+    assert_eq!(
+        direction(0.7, -0.6),
+        vec![Command::MoveDown, Command::MoveRight]
+    );
+}
+
+#[test]
+fn stick_moves_the_player() {
+    let mut world = world();
+    let mut stick = Stick::new();
+    let mut give_axis = |world: &mut World, axis: Axis, value: f32| {
+        let (pressed, released) = stick.give_axis(axis, value);
+        for command in pressed {
+            world.give_command_down(command);
+        }
+        for command in released {
+            world.give_command_up(command);
+        }
+    };
+
+    give_axis(&mut world, Axis::LeftStickY, 1.0);
+    assert_eq!(world.player.entity.action, Action::MovingUp);
+
+    give_axis(&mut world, Axis::LeftStickX, 1.0);
+    assert_eq!(world.player.entity.action, Action::MovingUpRight);
+
+    give_axis(&mut world, Axis::LeftStickY, 0.0);
+    assert_eq!(world.player.entity.action, Action::MovingRight);
+
+    give_axis(&mut world, Axis::LeftStickX, 0.0);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Right));
+}
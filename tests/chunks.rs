@@ -0,0 +1,207 @@
+use ggez::graphics::Rect;
+use ggez::nalgebra::Point2;
+
+use pax_romana::cell::Cell;
+use pax_romana::constants;
+use pax_romana::map::Map;
+use pax_romana::navigation::Navigation;
+use pax_romana::tileset::Tileset;
+
+mod common;
+use common::{csv, csv_map, tileset};
+
+#[test]
+fn infinite_maps_start_at_their_top_left_chunk() {
+    let map = format!(
+        r#"<map width="30" height="20" infinite="1">
+ <layer name="Ground" width="30" height="20">
+  <data encoding="csv">
+   <chunk x="-16" y="0" width="16" height="16">{}</chunk>
+   <chunk x="0" y="-16" width="16" height="16">{}</chunk>
+  </data>
+ </layer>
+ <objectgroup name="Objects">
+  <object id="1" name="well" x="-248" y="8"><point/></object>
+ </objectgroup>
+</map>"#,
+        csv(1, 256),
+        csv(2, 256)
+    );
+    let map = Map::new(map.as_bytes(), &tileset()).unwrap();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+    let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+
+    assert_eq!(
+        map.get_dimensions(),
+        (32.0 * pixel_width, 32.0 * pixel_height)
+    );
+
+    let cells: Vec<_> = map.get_layers()[0].get_cells().collect();
+    assert_eq!(cells.len(), 512);
+    for cell in cells.iter() {
+        match cell.id {
+            1 => assert!(cell.tile.0 < 16 && cell.tile.1 >= 16),
+            2 => assert!(cell.tile.0 >= 16 && cell.tile.1 < 16),
+            id => panic!("unexpected tile {}", id),
+        }
+    }
+
+    assert_eq!(
+        map.get_named_point("well"),
+        Some(Point2::new(
+            8.0 * constants::TILE_SCALE,
+            (16.0 * constants::TILE_HEIGHT + 8.0) * constants::TILE_SCALE
+        ))
+    );
+}
+
+#[test]
+fn only_chunks_near_the_view_stay_resident() {
+    let mut map = csv_map(160, 160, &[("Ground", &csv(1, 160 * 160))]);
+    let resident = |map: &Map| {
+        map.get_layers()[0]
+            .get_chunks()
+            .iter()
+            .filter(|chunk| chunk.is_resident())
+            .map(|chunk| chunk.position)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(map.get_layers()[0].get_chunks().len(), 100);
+    assert!(resident(&map).is_empty());
+    assert_eq!(map.get_layers()[0].get_visible_cells().count(), 0);
+
+    map.give_view(Rect::new(0.0, 0.0, 800.0, 600.0));
+    let near = resident(&map);
+    assert!(near.contains(&(0, 0)));
+    assert!(near.len() < 10);
+
+    // every visible cell comes from a resident chunk
+    let layer = &map.get_layers()[0];
+    assert!(layer.get_visible_cells().count() > 0);
+    for cell in layer.get_visible_cells() {
+        assert!(near.iter().any(|(x, y)| {
+            (*x..x + constants::CHUNK_SIZE).contains(&cell.tile.0)
+                && (*y..y + constants::CHUNK_SIZE).contains(&cell.tile.1)
+        }));
+    }
+
+    let (width, height) = map.get_dimensions();
+    map.give_view(Rect::new(width - 800.0, height - 600.0, 800.0, 600.0));
+    let far = resident(&map);
+    assert!(!far.contains(&(0, 0)));
+    assert!(far.contains(&(144, 144)));
+
+    // collision still covers the chunks that were dropped
+    assert_eq!(map.get_layers()[0].get_cells().count(), 160 * 160);
+}
+
+#[test]
+fn cells_of_a_tile_share_its_timeline() {
+    let mut tileset = tileset();
+    let map = csv_map(3, 1, &[("Statues", "35,35,15")]);
+    let cells: Vec<Cell> = map.get_layers()[0].get_cells().collect();
+    tileset.give_used_tiles(map.get_tile_ids()).unwrap();
+
+    // the walking player plays its frames in step on every cell
+    assert_eq!(
+        tileset.get_timeline(35).unwrap().get_frame(cells[0].phase),
+        0
+    );
+    tileset.update(0.15);
+    let walking = tileset.get_timeline(35).unwrap();
+    assert_eq!(walking.get_frame(cells[0].phase), 1);
+    assert_eq!(walking.get_frame(cells[1].phase), 1);
+
+    // flames are scrambled, each cell starts at its own point of the flicker
+    let flame = tileset.get_timeline(15).unwrap();
+    let frames: Vec<usize> = (0..10)
+        .map(|x| flame.get_frame(Cell::new(15, (x, 0)).phase))
+        .collect();
+    assert!(frames.contains(&0) && frames.contains(&1));
+
+    assert!(tileset.get_timeline(1).is_none());
+}
+
+#[test]
+fn timelines_are_built_per_clip_for_used_tiles() {
+    let mut tileset = tileset();
+
+    // both frames of the walking player play the same clip, flipped cells play their own
+    tileset
+        .give_used_tiles(vec![35, 36, 35 | constants::FLIP_H])
+        .unwrap();
+    let walking = tileset.get_timeline(35).unwrap();
+    assert!(std::ptr::eq(walking, tileset.get_timeline(36).unwrap()));
+    assert!(!std::ptr::eq(
+        walking,
+        tileset.get_timeline(35 | constants::FLIP_H).unwrap()
+    ));
+    assert!(tileset.get_timeline(37).is_none());
+
+    tileset.give_used_tiles(vec![37]).unwrap();
+    assert!(tileset.get_timeline(35).is_none());
+    assert!(tileset.get_timeline(37).is_some());
+}
+
+#[test]
+fn bad_events_only_fail_the_tiles_that_are_used() {
+    let tileset = r#"<tileset tilewidth="16" tileheight="16" tilecount="4" columns="2">
+ <image source="tileset.png" width="32" height="32"/>
+ <tile id="0">
+  <properties>
+   <property name="entity" value="ghost"/>
+   <property name="events" value="7:boo"/>
+  </properties>
+  <animation>
+   <frame tileid="0" duration="100"/>
+   <frame tileid="1" duration="100"/>
+  </animation>
+ </tile>
+</tileset>"#;
+    let mut tileset = Tileset::new(tileset.as_bytes()).unwrap();
+
+    assert!(tileset.give_used_tiles(vec![3, 4]).is_ok());
+    assert!(tileset.give_used_tiles(vec![1]).is_err());
+}
+
+#[test]
+fn far_apart_chunks_only_hold_their_own_collision() {
+    // a dense grid over both chunks would hold four billion tiles
+    let far = 4096 * constants::CHUNK_SIZE;
+    let map = format!(
+        r#"<map width="30" height="20" infinite="1">
+ <layer name="Ground" width="30" height="20">
+  <data encoding="csv">
+   <chunk x="0" y="0" width="16" height="16">{ground}</chunk>
+   <chunk x="{far}" y="{far}" width="16" height="16">{ground}</chunk>
+  </data>
+ </layer>
+ <layer name="Collision" width="30" height="20">
+  <data encoding="csv">
+   <chunk x="{far}" y="{far}" width="16" height="16">1,{empty}</chunk>
+  </data>
+ </layer>
+</map>"#,
+        far = far,
+        ground = csv(1, 256),
+        empty = csv(0, 255)
+    );
+    let map = Map::new(map.as_bytes(), &tileset()).unwrap();
+    let collision = map.get_collision();
+    let navigation = map.get_navigation();
+
+    assert!(collision.is_solid(far, far));
+    assert!(!collision.is_solid(0, 0));
+    assert!(!navigation.is_walkable((far, far)));
+    assert!(navigation.is_walkable((far + 1, far)));
+    assert!(navigation.is_walkable((0, 0)));
+
+    let path = navigation
+        .find_path(
+            Navigation::get_position((far + 1, far + 1)),
+            Navigation::get_position((far, far + 2)),
+        )
+        .unwrap();
+    assert_eq!(path.last(), Some(&Navigation::get_position((far, far + 2))));
+}
@@ -0,0 +1,50 @@
+// fixtures shared by the integration tests, each test file only uses some of them
+#![allow(dead_code)]
+
+use std::fs::File;
+
+use pax_romana::map::Map;
+use pax_romana::resources::Directory;
+use pax_romana::tileset::Tileset;
+use pax_romana::world::World;
+
+pub const RESOURCES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources");
+
+pub fn open(path: &str) -> File {
+    File::open(format!("{}/{}", RESOURCES, path)).unwrap()
+}
+
+pub fn tileset() -> Tileset {
+    Tileset::new(open("tileset.tsx")).unwrap()
+}
+
+pub fn world() -> World {
+    World::new(&mut Directory::new(RESOURCES)).unwrap()
+}
+
+pub fn csv(id: usize, count: usize) -> String {
+    vec![id.to_string(); count].join(",")
+}
+
+// a finite map with one csv encoded layer per (name, data) pair
+pub fn csv_map(width: usize, height: usize, layers: &[(&str, &str)]) -> Map {
+    let layers: String = layers
+        .iter()
+        .map(|(name, data)| {
+            format!(
+                r#"
+ <layer name="{}" width="{}" height="{}">
+  <data encoding="csv">{}</data>
+ </layer>"#,
+                name, width, height, data
+            )
+        })
+        .collect();
+    let map = format!(
+        r#"<map width="{}" height="{}">{}
+</map>"#,
+        width, height, layers
+    );
+
+    Map::new(map.as_bytes(), &tileset()).unwrap()
+}
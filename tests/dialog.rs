@@ -0,0 +1,180 @@
+use pax_romana::dialogbox::{Conversation, DialogTree};
+use pax_romana::gamestate::{Condition, Effect, GameState};
+
+mod common;
+use common::open;
+
+fn peasant() -> DialogTree {
+    DialogTree::from_reader(open("dialogtrees/peasant")).unwrap()
+}
+
+fn dialogtree(json: &str) -> DialogTree {
+    DialogTree::from_reader(json.as_bytes()).unwrap()
+}
+
+#[test]
+fn dialog_traversal() {
+    let mut gamestate = GameState::new();
+    let mut conversation = Conversation::new(peasant(), &mut gamestate).unwrap();
+
+    assert_eq!(conversation.get_text(), "Ave !");
+    assert_eq!(
+        conversation.get_responses(),
+        vec!["Unde venistis ?", "Esurio !", "Vale !"]
+    );
+
+    conversation.prev_response();
+    assert_eq!(conversation.get_selected_response(), 2);
+    conversation.next_response();
+    assert_eq!(conversation.get_selected_response(), 0);
+    conversation.next_response();
+
+    let conversation = conversation.choose_reponse(&mut gamestate).unwrap();
+    assert_eq!(conversation.get_text(), "Paenitet ! Cape panem.");
+    assert!(conversation.get_responses().is_empty());
+    assert_eq!(conversation.save().dialog, 2);
+    assert_eq!(gamestate.get_item_count("panis"), 1);
+
+    let conversation = Conversation::new(peasant(), &mut gamestate).unwrap();
+    assert_eq!(
+        conversation.get_responses(),
+        vec!["Unde venistis ?", "Vale !"]
+    );
+}
+
+#[test]
+fn dialog_tree_from_string() {
+    let json = r#"{
+        "0": { "text": "Salve", "responses": [{ "text": "Vale", "dialog": 1 }] },
+        "1": { "text": "Vale", "responses": [] }
+    }"#;
+    let mut gamestate = GameState::new();
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+
+    let conversation = conversation.choose_reponse(&mut gamestate).unwrap();
+    assert_eq!(conversation.get_text(), "Vale");
+
+    assert!(DialogTree::from_reader("not json".as_bytes()).is_err());
+}
+
+#[test]
+fn responses_are_hidden_by_their_conditions_and_their_dialogs() {
+    let json = r#"{
+        "0": {
+            "text": "Salve",
+            "responses": [
+                { "text": "Senator", "dialog": 1, "conditions": [{ "flag": "senator" }] },
+                { "text": "Quaestio", "dialog": 2 },
+                { "text": "Plebs", "dialog": 1, "conditions": [{ "not_flag": "senator" }] }
+            ]
+        },
+        "1": { "text": "Intra", "responses": [] },
+        "2": { "text": "Quaestio", "responses": [], "conditions": [{ "quest": "aqueduct" }] }
+    }"#;
+    let mut gamestate = GameState::new();
+
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+    assert_eq!(conversation.get_responses(), vec!["Plebs"]);
+
+    gamestate.give_flag("senator");
+    gamestate.give_quest("aqueduct");
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+    assert_eq!(conversation.get_responses(), vec!["Senator", "Quaestio"]);
+}
+
+#[test]
+fn effects_change_the_game_state() {
+    let json = r#"{
+        "0": {
+            "text": "Salve",
+            "responses": [{
+                "text": "Da mihi gladium",
+                "dialog": 1,
+                "effects": [{ "take_item": "denarius" }, { "set_flag": "armed" }]
+            }]
+        },
+        "1": {
+            "text": "Cape",
+            "responses": [],
+            "effects": [{ "give_item": "gladius" }, { "start_quest": "legio" }]
+        }
+    }"#;
+    let mut gamestate = GameState::new();
+    gamestate.give_item("denarius");
+
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+    conversation.choose_reponse(&mut gamestate).unwrap();
+
+    assert!(gamestate.check(&Condition::NotItem("denarius".to_string())));
+    assert!(gamestate.check(&Condition::Item("gladius".to_string())));
+    assert!(gamestate.has_flag("armed"));
+    assert!(gamestate.has_quest("legio"));
+}
+
+#[test]
+fn responses_can_end_the_conversation() {
+    let mut gamestate = GameState::new();
+    let mut conversation = Conversation::new(peasant(), &mut gamestate).unwrap();
+
+    conversation.prev_response();
+    assert!(conversation.choose_reponse(&mut gamestate).is_none());
+
+    let mut gamestate = GameState::new();
+    assert!(!gamestate.apply(&Effect::EndConversation));
+    assert!(gamestate.apply(&Effect::SetFlag("visited".to_string())));
+}
+
+#[test]
+fn merchant_closes_at_night() {
+    let merchant = DialogTree::from_reader(open("dialogtrees/merchant")).unwrap();
+    let mut gamestate = GameState::new();
+
+    gamestate.clock.give_hour(12.0);
+    assert!(gamestate.check(&Condition::Hours(7.0, 19.0)));
+    let conversation = Conversation::new(merchant.clone(), &mut gamestate).unwrap();
+    let conversation = conversation.choose_reponse(&mut gamestate).unwrap();
+    assert_eq!(conversation.get_text(), "Emite ! Optimae merces !");
+
+    gamestate.clock.give_hour(2.0);
+    assert!(gamestate.check(&Condition::Hours(19.0, 7.0)));
+    let conversation = Conversation::new(merchant, &mut gamestate).unwrap();
+    assert_eq!(conversation.get_responses(), vec!["Nihil."]);
+}
+
+#[test]
+fn gifts_are_guarded_by_their_conditions() {
+    let json = r#"{
+        "0": {
+            "text": "Salve",
+            "responses": [
+                { "text": "Panem !", "dialog": 1, "conditions": [{ "not_flag": "fed" }] },
+                { "text": "Vale", "effects": ["end_conversation"] }
+            ]
+        },
+        "1": {
+            "text": "Cape",
+            "responses": [],
+            "effects": [{ "give_item": "panis" }, { "set_flag": "fed" }]
+        }
+    }"#;
+    let mut gamestate = GameState::new();
+
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+    conversation.choose_reponse(&mut gamestate).unwrap();
+    assert_eq!(gamestate.get_item_count("panis"), 1);
+
+    // every npc sharing the tree can be opened again, the gift is gone
+    let conversation = Conversation::new(dialogtree(json), &mut gamestate).unwrap();
+    assert_eq!(conversation.get_responses(), vec!["Vale"]);
+    assert!(conversation.choose_reponse(&mut gamestate).is_none());
+    assert_eq!(gamestate.get_item_count("panis"), 1);
+}
+
+#[test]
+fn opening_dialogs_can_end_the_conversation_every_time() {
+    let json = r#"{ "0": { "text": "Abi !", "responses": [], "effects": ["end_conversation"] } }"#;
+    let mut gamestate = GameState::new();
+
+    assert!(Conversation::new(dialogtree(json), &mut gamestate).is_none());
+    assert!(Conversation::new(dialogtree(json), &mut gamestate).is_none());
+}
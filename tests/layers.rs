@@ -0,0 +1,63 @@
+use pax_romana::map::Map;
+
+mod common;
+use common::{csv_map, tileset};
+
+// the same 4x3 layer, with a horizontally flipped tile, in each of tiled's encodings
+const CSV: &str = "1,2,3,4,11,12,0,2147483649,21,22,31,32";
+const BASE64: &str = "AQAAAAIAAAADAAAABAAAAAsAAAAMAAAAAAAAAAEAAIAVAAAAFgAAAB8AAAAgAAAA";
+const ZLIB: &str = "eJxjZGBgYAJiZiBmAWJuIOZhgABGBoYGUSAtBsTyQKwAxAAQ1AEN";
+const GZIP: &str = "H4sIAAAAAAACA2NkYGBgAmJmIGYBYm4g5mGAAEYGhgZRIC0GxPJArADEAGYw7w0wAAAA";
+
+fn layer(encoding: &str, compression: Option<&str>, data: &str) -> Result<Vec<usize>, String> {
+    let compression = compression
+        .map(|compression| format!(r#" compression="{}""#, compression))
+        .unwrap_or_default();
+    let map = format!(
+        r#"<map width="4" height="3">
+ <layer name="Ground" width="4" height="3">
+  <data encoding="{}"{}>
+   {}
+  </data>
+ </layer>
+</map>"#,
+        encoding, compression, data
+    );
+
+    let map = Map::new(map.as_bytes(), &tileset()).map_err(|e| e.to_string())?;
+    let mut cells: Vec<_> = map.get_layers()[0].get_cells().collect();
+    cells.sort_by_key(|cell| (cell.tile.1, cell.tile.0));
+    Ok(cells.into_iter().map(|cell| cell.id).collect())
+}
+
+#[test]
+fn encodings_decode_to_the_same_tiles() {
+    let csv = layer("csv", None, CSV).unwrap();
+    assert_eq!(csv.len(), 12);
+    assert_eq!(csv[7], 1 | pax_romana::constants::FLIP_H);
+
+    assert_eq!(layer("base64", None, BASE64).unwrap(), csv);
+    assert_eq!(layer("base64", Some("zlib"), ZLIB).unwrap(), csv);
+    assert_eq!(layer("base64", Some("gzip"), GZIP).unwrap(), csv);
+}
+
+#[test]
+fn bad_layer_data_is_an_error() {
+    assert!(layer("xml", None, CSV).is_err());
+    assert!(layer("base64", Some("zstd"), BASE64).is_err());
+    assert!(layer("base64", None, "not base64!").is_err());
+    assert!(layer("base64", Some("zlib"), BASE64).is_err());
+    assert!(layer("base64", Some("gzip"), ZLIB).is_err());
+}
+
+#[test]
+fn rows_follow_the_map_width() {
+    let map = csv_map(4, 3, &[("Ground", "1,2,3,4,5,6,7,8,9,10,11,12")]);
+
+    let cells: Vec<_> = map.get_layers()[0].get_cells().collect();
+    assert_eq!(cells.len(), 12);
+    for cell in cells {
+        let i = cell.id - 1;
+        assert_eq!(cell.tile, (i % 4, i / 4));
+    }
+}
@@ -0,0 +1,134 @@
+use pax_romana::map::Map;
+use pax_romana::navigation::Navigation;
+
+mod common;
+use common::{csv, csv_map};
+
+fn map(collision: &str) -> Map {
+    sized_map(5, 5, collision)
+}
+
+fn sized_map(width: usize, height: usize, collision: &str) -> Map {
+    csv_map(
+        width,
+        height,
+        &[
+            ("Ground", &csv(1, width * height)),
+            ("Collision", collision),
+        ],
+    )
+}
+
+fn is_adjacent(a: (usize, usize), b: (usize, usize)) -> bool {
+    a != b && (a.0 as isize - b.0 as isize).abs() <= 1 && (a.1 as isize - b.1 as isize).abs() <= 1
+}
+
+#[test]
+fn path_goes_around_walls() {
+    let map = map("0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,0,0,0");
+    let navigation = map.get_navigation();
+    let collision = map.get_collision();
+
+    let start = Navigation::get_position((0, 0));
+    let goal = Navigation::get_position((3, 0));
+    let path = navigation.find_path(start, goal).unwrap();
+
+    assert_eq!(path.last(), Some(&goal));
+    assert!(path
+        .iter()
+        .any(|waypoint| navigation.get_tile(*waypoint) == Some((2, 4))));
+
+    let mut previous = navigation.get_tile(start).unwrap();
+    for waypoint in path.iter() {
+        assert!(!collision.collides(*waypoint));
+
+        let tile = navigation.get_tile(*waypoint).unwrap();
+        assert!(is_adjacent(previous, tile));
+        previous = tile;
+    }
+}
+
+#[test]
+fn path_does_not_cut_corners() {
+    let map = map("0,0,0,0,0,
+                   0,1,0,0,0,
+                   0,0,0,0,0,
+                   0,0,0,0,0,
+                   0,0,0,0,0");
+    let navigation = map.get_navigation();
+
+    let path = navigation
+        .find_path(
+            Navigation::get_position((0, 1)),
+            Navigation::get_position((1, 0)),
+        )
+        .unwrap();
+
+    assert_eq!(path.len(), 2);
+    assert_eq!(navigation.get_tile(path[0]), Some((0, 0)));
+}
+
+#[test]
+fn unreachable_destinations_have_no_path() {
+    let map = map("0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,1,0,0,
+                   0,0,1,0,0");
+    let navigation = map.get_navigation();
+    let start = Navigation::get_position((0, 0));
+
+    assert!(navigation
+        .find_path(start, Navigation::get_position((3, 3)))
+        .is_none());
+    assert!(navigation
+        .find_path(start, Navigation::get_position((2, 2)))
+        .is_none());
+    assert!(navigation
+        .find_path(start, Navigation::get_position((40, 40)))
+        .is_none());
+}
+
+#[test]
+fn tiles_round_trip_through_positions() {
+    let navigation = map("0,0,0,0,0,
+                          0,0,0,0,0,
+                          0,0,0,0,0,
+                          0,0,0,0,0,
+                          0,0,0,0,0")
+    .get_navigation();
+
+    for y in 0..5 {
+        for x in 0..4 {
+            let tile = (x, y);
+            assert!(navigation.is_walkable(tile));
+            assert_eq!(
+                navigation.get_tile(Navigation::get_position(tile)),
+                Some(tile)
+            );
+        }
+    }
+}
+
+#[test]
+fn searches_give_up_on_long_detours() {
+    // a wall down the middle of the map, only open at the bottom
+    let collision = (0..64)
+        .flat_map(|y| (0..64).map(move |x| if x == 32 && y < 63 { "1" } else { "0" }))
+        .collect::<Vec<_>>()
+        .join(",");
+    let map = sized_map(64, 64, &collision);
+    let navigation = map.get_navigation();
+    let start = Navigation::get_position((30, 0));
+
+    assert!(navigation
+        .find_path(start, Navigation::get_position((20, 10)))
+        .is_some());
+    assert!(navigation
+        .find_path(start, Navigation::get_position((34, 0)))
+        .is_none());
+}
@@ -0,0 +1,239 @@
+use pax_romana::bindings::Command;
+use pax_romana::constants;
+use pax_romana::entity::{Action, Direction, Operable};
+use pax_romana::world::World;
+
+mod common;
+use common::world;
+
+const DIRECTIONS: [Command; 4] = [
+    Command::MoveUp,
+    Command::MoveDown,
+    Command::MoveLeft,
+    Command::MoveRight,
+];
+
+fn permutations(commands: &[Command]) -> Vec<Vec<Command>> {
+    if commands.is_empty() {
+        return vec![Vec::new()];
+    }
+
+    let mut permutations = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        let mut rest = commands.to_vec();
+        rest.remove(i);
+        for mut permutation in self::permutations(&rest) {
+            permutation.insert(0, *command);
+            permutations.push(permutation);
+        }
+    }
+    permutations
+}
+
+// what every set of held directions moves the player, None standing still
+fn moves() -> Vec<(Vec<Command>, Option<Action>)> {
+    use Command::*;
+    vec![
+        (vec![], None),
+        (vec![MoveUp], Some(Action::MovingUp)),
+        (vec![MoveDown], Some(Action::MovingDown)),
+        (vec![MoveLeft], Some(Action::MovingLeft)),
+        (vec![MoveRight], Some(Action::MovingRight)),
+        (vec![MoveUp, MoveDown], None),
+        (vec![MoveLeft, MoveRight], None),
+        (vec![MoveUp, MoveLeft], Some(Action::MovingUpLeft)),
+        (vec![MoveUp, MoveRight], Some(Action::MovingUpRight)),
+        (vec![MoveDown, MoveLeft], Some(Action::MovingDownLeft)),
+        (vec![MoveDown, MoveRight], Some(Action::MovingDownRight)),
+        (vec![MoveUp, MoveLeft, MoveRight], Some(Action::MovingUp)),
+        (
+            vec![MoveDown, MoveLeft, MoveRight],
+            Some(Action::MovingDown),
+        ),
+        (vec![MoveUp, MoveDown, MoveLeft], Some(Action::MovingLeft)),
+        (vec![MoveUp, MoveDown, MoveRight], Some(Action::MovingRight)),
+        (vec![MoveUp, MoveDown, MoveLeft, MoveRight], None),
+    ]
+}
+
+// the player's action after each press (true) or release (false)
+fn play(world: &mut World, steps: &[(Command, bool)]) -> Vec<Action> {
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+    let mut actions = Vec::new();
+    for (command, pressed) in steps {
+        if *pressed {
+            world.give_command_down(*command);
+        } else {
+            world.give_command_up(*command);
+        }
+        world.update(delta);
+        actions.push(world.player.entity.action.clone());
+    }
+    actions
+}
+
+#[test]
+fn awkward_press_and_release_orders() {
+    use Command::*;
+    let mut world = world();
+
+    let actions = play(
+        &mut world,
+        &[
+            (MoveUp, true),
+            (MoveLeft, true),
+            (MoveRight, true),
+            (MoveLeft, false),
+            (MoveUp, false),
+            (MoveRight, false),
+        ],
+    );
+    assert_eq!(
+        actions,
+        vec![
+            Action::MovingUp,
+            Action::MovingUpLeft,
+            Action::MovingUp,
+            Action::MovingUpRight,
+            Action::MovingRight,
+            Action::Idle(Direction::Right),
+        ]
+    );
+
+    let actions = play(
+        &mut world,
+        &[
+            (MoveDown, true),
+            (MoveLeft, true),
+            (MoveLeft, false),
+            (MoveDown, false),
+        ],
+    );
+    assert_eq!(
+        actions,
+        vec![
+            Action::MovingDown,
+            Action::MovingDownLeft,
+            Action::MovingDown,
+            Action::Idle(Direction::Down),
+        ]
+    );
+
+    let actions = play(
+        &mut world,
+        &[
+            (MoveDown, true),
+            (MoveRight, true),
+            (MoveUp, true),
+            (MoveRight, false),
+            (MoveUp, false),
+            (MoveDown, false),
+        ],
+    );
+    assert_eq!(
+        actions,
+        vec![
+            Action::MovingDown,
+            Action::MovingDownRight,
+            Action::MovingRight,
+            Action::Idle(Direction::Right),
+            Action::MovingDown,
+            Action::Idle(Direction::Down),
+        ]
+    );
+}
+
+#[test]
+fn every_press_and_release_order_moves_the_held_way() {
+    let moves = moves();
+    let mut world = world();
+
+    // every order ends with nothing held, so one world is reused throughout
+    for presses in permutations(&DIRECTIONS) {
+        for releases in permutations(&DIRECTIONS) {
+            let mut held = Vec::new();
+            let mut previous = world.player.entity.action.clone();
+
+            let steps: Vec<(Command, bool)> = presses
+                .iter()
+                .map(|command| (*command, true))
+                .chain(releases.iter().map(|command| (*command, false)))
+                .collect();
+            for ((command, pressed), action) in steps.iter().zip(play(&mut world, &steps)) {
+                if *pressed {
+                    held.push(*command);
+                } else {
+                    held.retain(|held| held != command);
+                }
+
+                let (_, moving) = moves
+                    .iter()
+                    .find(|(commands, _)| {
+                        commands.len() == held.len()
+                            && commands.iter().all(|command| held.contains(command))
+                    })
+                    .unwrap();
+                // standing still faces the way the player last went
+                let expected = moving
+                    .clone()
+                    .unwrap_or_else(|| Action::Idle(previous.get_direction()));
+                assert_eq!(
+                    action, expected,
+                    "pressed {:?} then released {:?}, holding {:?}",
+                    presses, releases, held
+                );
+                previous = action;
+            }
+        }
+    }
+}
+
+#[test]
+fn opposite_directions_cancel_out() {
+    let mut world = world();
+
+    world.give_command_down(Command::MoveUp);
+    world.give_command_down(Command::MoveLeft);
+    world.give_command_down(Command::MoveRight);
+    assert_eq!(world.player.entity.action, Action::MovingUp);
+
+    world.give_command_down(Command::MoveDown);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Up));
+
+    world.give_command_up(Command::MoveLeft);
+    assert_eq!(world.player.entity.action, Action::MovingRight);
+}
+
+#[test]
+fn releasing_the_last_direction_faces_where_the_player_went() {
+    let mut world = world();
+
+    world.give_command_down(Command::MoveLeft);
+    world.give_command_down(Command::MoveDown);
+    world.give_command_up(Command::MoveLeft);
+    assert_eq!(world.player.entity.action, Action::MovingDown);
+
+    world.give_command_up(Command::MoveDown);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Down));
+
+    world.give_command_down(Command::MoveUp);
+    world.give_command_down(Command::MoveRight);
+    world.give_command_up(Command::MoveUp);
+    world.give_command_up(Command::MoveRight);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Right));
+}
+
+#[test]
+fn a_direction_stays_held_while_any_binding_holds_it() {
+    let mut world = world();
+
+    // e.g. both the W key and the d-pad
+    world.give_command_down(Command::MoveUp);
+    world.give_command_down(Command::MoveUp);
+    world.give_command_up(Command::MoveUp);
+    assert_eq!(world.player.entity.action, Action::MovingUp);
+
+    world.give_command_up(Command::MoveUp);
+    world.give_command_up(Command::MoveUp);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Up));
+}
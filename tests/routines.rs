@@ -0,0 +1,229 @@
+use ggez::nalgebra::{distance, Point2};
+
+use pax_romana::character::{Appointment, Routine};
+use pax_romana::clock::Clock;
+use pax_romana::constants;
+use pax_romana::entity::Operable;
+use pax_romana::gamestate::GameState;
+use pax_romana::map::{Map, Portal};
+use pax_romana::resources::Directory;
+use pax_romana::save::BehaviorState;
+use pax_romana::world::World;
+
+mod common;
+use common::{open, tileset, RESOURCES};
+
+fn villa() -> (World, Map) {
+    let mut resources = Directory::new(RESOURCES);
+    let mut world = World::new(&mut resources).unwrap();
+    world
+        .travel(
+            &mut resources,
+            &Portal {
+                map: "villa".to_string(),
+                destination: None,
+            },
+        )
+        .unwrap();
+
+    let tileset = tileset();
+    let map = Map::new(open("villa.tmx"), &tileset).unwrap();
+
+    (world, map)
+}
+
+fn custos(world: &World, map: &Map) -> Point2<f32> {
+    let post = map.get_named_point("custos").unwrap();
+    world
+        .get_npcs()
+        .iter()
+        .find(|npc| npc.entity.spawn == post)
+        .unwrap()
+        .entity
+        .position
+}
+
+// steps the world until the condition holds, giving up after the given seconds
+fn step_until(world: &mut World, seconds: f32, condition: impl Fn(&World) -> bool) -> bool {
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+    for _ in 0..(seconds / delta) as usize {
+        world.update(delta);
+        if condition(world) {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn routines_parse_from_names_and_json() {
+    assert_eq!(Routine::parse("stand").unwrap(), Routine::Stand);
+    assert_eq!(
+        Routine::parse(r#"{"post": "forum"}"#).unwrap(),
+        Routine::Post("forum".to_string())
+    );
+    assert_eq!(
+        Routine::parse(r#"{"patrol": ["a", "b"]}"#).unwrap(),
+        Routine::Patrol(vec!["a".to_string(), "b".to_string()])
+    );
+    assert!(Routine::parse("dance").is_err());
+}
+
+#[test]
+fn schedules_pick_the_latest_appointment() {
+    let schedule = Routine::Schedule(vec![
+        Appointment {
+            hour: 6.0,
+            routine: Routine::Wander,
+        },
+        Appointment {
+            hour: 12.0,
+            routine: Routine::Post("forum".to_string()),
+        },
+        Appointment {
+            hour: 20.0,
+            routine: Routine::Stand,
+        },
+    ]);
+
+    assert_eq!(schedule.at(3.0), &Routine::Stand);
+    assert_eq!(schedule.at(6.0), &Routine::Wander);
+    assert_eq!(schedule.at(13.5), &Routine::Post("forum".to_string()));
+    assert_eq!(schedule.at(23.0), &Routine::Stand);
+    assert_eq!(schedule.get_points(), vec!["forum"]);
+}
+
+#[test]
+fn clock_advances_through_the_day() {
+    let mut clock = Clock::new(constants::START_HOUR);
+    assert_eq!(clock.get_day(), 0);
+    assert!((clock.get_hour() - constants::START_HOUR).abs() < constants::FLOAT_PRECISION);
+
+    clock.update(constants::DAY_LENGTH / 2.0);
+    assert!((clock.get_hour() - (constants::START_HOUR + 12.0)).abs() < 0.01);
+
+    clock.update(constants::DAY_LENGTH / 2.0);
+    assert_eq!(clock.get_day(), 1);
+
+    clock.give_hour(22.0);
+    assert_eq!(clock.get_day(), 1);
+    assert!((clock.get_hour() - 22.0).abs() < 0.01);
+}
+
+#[test]
+fn clock_keeps_time_after_many_days() {
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+    let mut clock = Clock::new(constants::START_HOUR);
+    clock.give_day(10_000);
+
+    // an in-game hour of steps moves the clock by an hour
+    for _ in 0..(constants::DAY_LENGTH / 24.0 / delta).round() as usize {
+        clock.update(delta);
+    }
+    assert_eq!(clock.get_day(), 10_000);
+    assert!((clock.get_hour() - (constants::START_HOUR + 1.0)).abs() < 0.01);
+
+    // saves from before days were counted keep their day
+    let old: Clock =
+        serde_json::from_str(&format!(r#"{{"time": {}}}"#, constants::DAY_LENGTH * 3.5)).unwrap();
+    assert_eq!(old.get_day(), 3);
+    assert!((old.get_hour() - 12.0).abs() < 0.01);
+}
+
+#[test]
+fn night_is_tinted_darker_than_day() {
+    let brightness = |hour: f32| {
+        let tint = Clock::new(hour).get_tint();
+        tint.r + tint.g + tint.b
+    };
+
+    assert!((brightness(13.0) - 3.0).abs() < constants::FLOAT_PRECISION);
+    assert!(brightness(1.0) < brightness(6.0));
+    assert!(brightness(6.0) < brightness(13.0));
+    assert!(brightness(22.0) < brightness(20.0));
+}
+
+#[test]
+fn clock_is_saved_with_the_gamestate() {
+    let mut gamestate = GameState::new();
+    gamestate.clock.update(constants::DAY_LENGTH * 1.5);
+
+    let json = serde_json::to_string(&gamestate).unwrap();
+    let loaded: GameState = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.clock, gamestate.clock);
+    assert_eq!(loaded.clock.get_day(), 1);
+
+    // saves from before the clock start the day over
+    let old: GameState =
+        serde_json::from_str(r#"{"flags": [], "items": {}, "quests": []}"#).unwrap();
+    assert_eq!(old.clock, Clock::new(constants::START_HOUR));
+}
+
+#[test]
+fn soldier_patrols_by_day() {
+    let (mut world, map) = villa();
+    let entrance = map.get_named_point("entrance").unwrap();
+    let vilicus = map.get_named_point("vilicus").unwrap();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+
+    assert!(step_until(&mut world, 30.0, |world| {
+        distance(&custos(world, &map), &entrance) < pixel_width
+    }));
+    assert!(step_until(&mut world, 30.0, |world| {
+        distance(&custos(world, &map), &vilicus) < pixel_width
+    }));
+    assert!(step_until(&mut world, 30.0, |world| {
+        distance(&custos(world, &map), &entrance) < pixel_width
+    }));
+}
+
+#[test]
+fn soldier_returns_to_post_at_night() {
+    let (mut world, map) = villa();
+    let post = map.get_named_point("custos").unwrap();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+
+    assert!(step_until(&mut world, 5.0, |world| {
+        distance(&custos(world, &map), &post) > pixel_width
+    }));
+
+    world.gamestate.clock.give_hour(21.0);
+    assert!(step_until(&mut world, 30.0, |world| {
+        distance(&custos(world, &map), &post) < pixel_width
+    }));
+
+    step_until(&mut world, 5.0, |_| false);
+    let position = custos(&world, &map);
+    assert!(distance(&position, &post) < pixel_width);
+    assert!(!step_until(&mut world, 5.0, |world| {
+        custos(world, &map) != position
+    }));
+}
+
+#[test]
+fn npcs_start_on_the_routine_of_the_hour() {
+    let mut resources = Directory::new(RESOURCES);
+    let mut world = World::new(&mut resources).unwrap();
+    world.gamestate.clock.give_hour(21.0);
+    world
+        .travel(
+            &mut resources,
+            &Portal {
+                map: "villa".to_string(),
+                destination: None,
+            },
+        )
+        .unwrap();
+
+    let map = villa().1;
+    let post = map.get_named_point("custos").unwrap();
+    let custos = world
+        .get_npcs()
+        .iter()
+        .find(|npc| npc.entity.spawn == post)
+        .unwrap();
+    assert!(!matches!(
+        custos.save().behavior,
+        BehaviorState::Patrolling(_)
+    ));
+}
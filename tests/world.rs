@@ -0,0 +1,497 @@
+use ggez::graphics::Rect;
+use ggez::nalgebra::Point2;
+
+use pax_romana::bindings::Command;
+use pax_romana::camera::Camera;
+use pax_romana::character::{Character, Routine};
+use pax_romana::constants;
+use pax_romana::dialogbox::Conversation;
+use pax_romana::entity::{Action, Direction, Entity, Operable};
+use pax_romana::error::Error;
+use pax_romana::map::{Map, Portal};
+use pax_romana::npc::NPC;
+use pax_romana::resources::Directory;
+use pax_romana::save::Save;
+use pax_romana::world::World;
+
+mod common;
+use common::{csv, csv_map, open, tileset, world, RESOURCES};
+
+fn map() -> Map {
+    Map::new(open("map.tmx"), &tileset()).unwrap()
+}
+
+fn step(world: &mut World, seconds: f32) {
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+    for _ in 0..(seconds / delta) as usize {
+        world.update(delta);
+    }
+}
+
+fn open_map() -> Map {
+    csv_map(4, 3, &[("Ground", &csv(1, 12))])
+}
+
+#[test]
+fn map_has_spawn_points() {
+    let map = map();
+    let (width, height) = map.get_dimensions();
+
+    let players = map.get_spawn_points("player");
+    let peasants = map.get_spawn_points("peasant");
+
+    assert!(!players.is_empty());
+    assert!(!peasants.is_empty());
+    for point in players.iter().chain(peasants.iter()) {
+        assert!(point.x >= 0.0 && point.x <= width);
+        assert!(point.y >= 0.0 && point.y <= height);
+    }
+}
+
+#[test]
+fn map_has_named_points_and_portals() {
+    let map = map();
+
+    let north_road = map.get_named_point("north-road").unwrap();
+    assert_eq!(
+        north_road,
+        Point2::new(168.0 * constants::TILE_SCALE, 40.0 * constants::TILE_SCALE)
+    );
+
+    let villa_gate = map
+        .get_objects()
+        .iter()
+        .find(|object| object.name == "villa-gate")
+        .unwrap();
+    let portal = map.get_portal(villa_gate.get_center()).unwrap();
+    assert_eq!(portal.map, "villa");
+    assert_eq!(portal.destination.as_deref(), Some("entrance"));
+}
+
+#[test]
+fn world_starts_at_player_spawn() {
+    let world = world();
+
+    assert_eq!(world.get_current_map(), constants::START_MAP);
+    assert!(map()
+        .get_spawn_points(constants::PLAYER)
+        .contains(&world.player.get_position()));
+}
+
+#[test]
+fn movement_is_clamped_to_the_map() {
+    let map = open_map();
+    let (width, height) = map.get_dimensions();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+    let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+
+    let mut entity = Entity::new(
+        Point2::new(width / 2.0, height / 2.0),
+        180.0,
+        map.get_collision(),
+    );
+
+    entity.action = Action::MovingUpLeft;
+    for _ in 0..constants::UPDATES_PER_SECOND * 5 {
+        entity.update(delta);
+    }
+    assert_eq!(entity.position, Point2::new(0.0, 0.0));
+
+    entity.action = Action::MovingDownRight;
+    for _ in 0..constants::UPDATES_PER_SECOND * 5 {
+        entity.update(delta);
+    }
+    assert_eq!(
+        entity.position,
+        Point2::new(width - pixel_width, height - pixel_height)
+    );
+}
+
+#[test]
+fn movement_stops_at_solid_tiles() {
+    let collision = map().get_collision();
+    let (width, height) = collision.get_dimensions();
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+
+    let mut entity = Entity::new(
+        Point2::new(width / 2.0, height / 2.0),
+        180.0,
+        collision.clone(),
+    );
+    entity.action = Action::MovingUp;
+    for _ in 0..constants::UPDATES_PER_SECOND * 5 {
+        entity.update(delta);
+        assert!(!collision.collides(entity.position));
+    }
+}
+
+#[test]
+fn world_steps_without_leaving_the_map() {
+    let mut world = world();
+    let (width, height) = world.get_dimensions();
+
+    world.give_command_down(Command::MoveDown);
+    world.give_command_down(Command::MoveRight);
+    step(&mut world, 10.0);
+    assert_eq!(world.player.entity.action, Action::MovingDownRight);
+
+    let position = world.player.get_position();
+    assert!(position.x >= 0.0 && position.x <= width);
+    assert!(position.y >= 0.0 && position.y <= height);
+}
+
+#[test]
+fn player_can_talk_to_nearby_npc() {
+    let mut world = world();
+    assert!(world.get_dialogtree().is_none());
+
+    let shepherd = map().get_spawn_points("peasant")[0];
+    world.player.entity.position = shepherd;
+    assert!(world.get_dialogtree().is_some());
+    assert!(world.player_in_talking_range());
+}
+
+#[test]
+fn npcs_use_their_own_name_and_dialog_tree() {
+    let mut world = world();
+
+    let map = map();
+    let position = map.get_named_point("shepherd").unwrap();
+    let shepherd = map
+        .get_spawns()
+        .into_iter()
+        .find(|spawn| spawn.position == position)
+        .unwrap();
+    assert_eq!(shepherd.name.as_deref(), Some("Tityrus"));
+    assert_eq!(shepherd.dialogtree.as_deref(), Some("tityrus"));
+
+    world.player.entity.position = shepherd.position;
+    let dialogtree = world.get_dialogtree().unwrap();
+    let conversation = Conversation::new(dialogtree, &mut world.gamestate).unwrap();
+    assert_eq!(conversation.get_speaker(), "Tityrus");
+    assert_eq!(conversation.get_text(), "Salve, viator.");
+}
+
+#[test]
+fn characters_are_loaded_from_the_registry() {
+    let characters = Character::load_all(&mut Directory::new(RESOURCES)).unwrap();
+
+    let soldier = Character::get(&characters, "soldier").unwrap();
+    assert_eq!(soldier.name, "soldier");
+    assert_eq!(soldier.routine, Routine::Stand);
+    assert_eq!(soldier.get_dialogtree(), "soldier");
+    assert_eq!(soldier.get_entity(), "soldier");
+
+    let merchant = Character::get(&characters, "merchant").unwrap();
+    assert_eq!(merchant.get_entity(), "peasant");
+
+    assert!(Character::get(&characters, "gladiator").is_err());
+}
+
+#[test]
+fn spawns_build_the_matching_npcs() {
+    let mut resources = Directory::new(RESOURCES);
+    let tileset = tileset();
+    let characters = Character::load_all(&mut resources).unwrap();
+    let spawns = |characters: &[&str]| {
+        let objects: String = characters
+            .iter()
+            .enumerate()
+            .map(|(i, character)| {
+                format!(
+                    r#"<object id="{}" x="16" y="16"><properties><property name="spawn" value="{}"/></properties><point/></object>"#,
+                    i + 1,
+                    character
+                )
+            })
+            .collect();
+        let map = format!(
+            r#"<map width="2" height="2"><objectgroup name="Objects">{}</objectgroup></map>"#,
+            objects
+        );
+        Map::new(map.as_bytes(), &tileset).unwrap()
+    };
+
+    let map = spawns(&["player", "soldier", "merchant", "senator"]);
+    let npcs = NPC::build_npcs(
+        &mut resources,
+        &tileset,
+        &characters,
+        &map,
+        constants::START_HOUR,
+    )
+    .unwrap();
+    assert_eq!(npcs.len(), 3);
+
+    let map = spawns(&["gladiator"]);
+    assert!(NPC::build_npcs(
+        &mut resources,
+        &tileset,
+        &characters,
+        &map,
+        constants::START_HOUR
+    )
+    .is_err());
+}
+
+#[test]
+fn travelling_spawns_the_next_maps_npcs() {
+    let mut resources = Directory::new(RESOURCES);
+    let mut world = world();
+
+    world
+        .travel(
+            &mut resources,
+            &Portal {
+                map: "villa".to_string(),
+                destination: Some("custos".to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(world.get_current_map(), "villa");
+
+    let dialogtree = world.get_dialogtree().unwrap();
+    let conversation = Conversation::new(dialogtree, &mut world.gamestate).unwrap();
+    assert_eq!(conversation.get_speaker(), "Custos");
+    assert_eq!(conversation.get_text(), "Siste ! Quis es ?");
+}
+
+#[test]
+fn failed_travels_keep_the_current_map() {
+    let mut resources = Directory::new(RESOURCES);
+    let mut world = world();
+    let map = world.get_current_map().to_string();
+    let position = world.player.get_position();
+    let npcs = world.get_npcs().len();
+
+    let portal = Portal {
+        map: "carthago".to_string(),
+        destination: None,
+    };
+    match world.travel(&mut resources, &portal) {
+        Err(Error::UnknownMap(name)) => assert_eq!(name, "carthago"),
+        _ => panic!("travelled to an unknown map"),
+    }
+    assert_eq!(world.get_current_map(), map);
+    assert_eq!(world.player.get_position(), position);
+    assert_eq!(world.get_npcs().len(), npcs);
+}
+
+#[test]
+fn layers_can_be_drawn_above_entities() {
+    let map = r#"<map width="2" height="1">
+ <layer name="Ground" width="2" height="1">
+  <data encoding="csv">1,1</data>
+ </layer>
+ <layer name="Canopy" width="2" height="1">
+  <properties>
+   <property name="above" type="bool" value="true"/>
+  </properties>
+  <data encoding="csv">11,12</data>
+ </layer>
+</map>"#;
+    let map = Map::new(map.as_bytes(), &tileset()).unwrap();
+
+    let above: Vec<(&str, bool)> = map
+        .get_layers()
+        .iter()
+        .map(|layer| (layer.name.as_str(), layer.above))
+        .collect();
+    assert_eq!(above, vec![("Ground", false), ("Canopy", true)]);
+
+    assert!(self::map().get_layers().iter().any(|layer| layer.above));
+}
+
+#[test]
+fn entities_are_drawn_from_back_to_front() {
+    let mut world = world();
+
+    world.player.entity.position.y = 0.0;
+    let order = world.get_draw_order();
+    assert!(order.len() > 1);
+    assert_eq!(order[0], world.player.get_position());
+    assert!(order.windows(2).all(|pair| pair[0].y <= pair[1].y));
+
+    let (_, height) = world.get_dimensions();
+    world.player.entity.position.y = height;
+    assert_eq!(
+        world.get_draw_order().last(),
+        Some(&world.player.get_position())
+    );
+}
+
+#[test]
+fn only_cells_in_view_are_drawn() {
+    let mut map = map();
+    // nothing is built until the camera gives a view
+    assert_eq!(map.get_layers()[0].get_visible_cells().count(), 0);
+    assert_eq!(map.get_layers()[0].get_cells().count(), 20 * 20);
+
+    let mut camera = Camera::new(map.get_dimensions());
+    camera.give_center(Point2::new(0.0, 0.0));
+    let view = camera.get_view();
+    assert_eq!((view.x, view.y), (0.0, 0.0));
+
+    map.give_view(view);
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+    let pixel_height = constants::TILE_HEIGHT * constants::TILE_SCALE;
+    let columns = ((view.w / pixel_width).ceil() as usize + constants::VIEW_MARGIN).min(20);
+    let rows = ((view.h / pixel_height).ceil() as usize + constants::VIEW_MARGIN).min(20);
+
+    let layer = &map.get_layers()[0];
+    assert_eq!(layer.get_visible_cells().count(), columns * rows);
+    assert!(columns * rows < 20 * 20);
+    for cell in layer.get_visible_cells() {
+        assert!(cell.destination.x < view.right() + pixel_width * 3.0);
+        assert!(cell.destination.y < view.bottom() + pixel_height * 3.0);
+    }
+
+    // a view off the map shows nothing
+    map.give_view(Rect::new(-10_000.0, -10_000.0, 100.0, 100.0));
+    assert_eq!(map.get_layers()[0].get_visible_cells().count(), 0);
+}
+
+// an open 6x6 map with a wall along column 3
+fn walled_map() -> Map {
+    let collision = "0,0,0,1,0,0,
+                     0,0,0,1,0,0,
+                     0,0,0,1,0,0,
+                     0,0,0,1,0,0,
+                     0,0,0,1,0,0,
+                     0,0,0,1,0,0";
+    csv_map(6, 6, &[("Ground", &csv(1, 36)), ("Collision", collision)])
+}
+
+fn walk(entity: &mut Entity, action: Action, seconds: f32) {
+    let delta = 1.0 / constants::UPDATES_PER_SECOND as f32;
+    entity.action = action;
+    for _ in 0..(seconds / delta) as usize {
+        entity.update(delta);
+    }
+}
+
+#[test]
+fn walls_stop_movement() {
+    let map = walled_map();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+    let mut entity = Entity::new(
+        Point2::new(pixel_width, pixel_width * 2.0),
+        180.0,
+        map.get_collision(),
+    );
+
+    walk(&mut entity, Action::MovingRight, 2.0);
+    assert!(!map.collides(entity.position));
+    assert!(entity.position.x < pixel_width * 3.0);
+    assert!(entity.position.x > pixel_width * 2.0);
+}
+
+#[test]
+fn diagonal_moves_slide_along_walls() {
+    let map = walled_map();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+    let start = Point2::new(pixel_width * 2.5, pixel_width);
+    let mut entity = Entity::new(start, 180.0, map.get_collision());
+
+    walk(&mut entity, Action::MovingDownRight, 0.5);
+    assert!(!map.collides(entity.position));
+    assert!(entity.position.y > start.y + pixel_width);
+    assert!(entity.position.x < pixel_width * 3.0);
+}
+
+#[test]
+fn entities_inside_walls_can_only_walk_out() {
+    let map = walled_map();
+    let collision = map.get_collision();
+    let pixel_width = constants::TILE_WIDTH * constants::TILE_SCALE;
+
+    // straddling the wall's left edge
+    let start = Point2::new(pixel_width * 3.0, pixel_width * 2.0);
+    let mut entity = Entity::new(start, 180.0, map.get_collision());
+    let overlap = collision.get_overlap(start);
+    assert!(overlap > 0.0);
+
+    walk(&mut entity, Action::MovingRight, 0.1);
+    assert_eq!(entity.position, start);
+
+    walk(&mut entity, Action::MovingLeft, 0.5);
+    assert!(!map.collides(entity.position));
+}
+
+#[test]
+fn empty_polygons_are_rejected() {
+    let polygon = |points: &str| {
+        let map = format!(
+            r#"<map width="2" height="2"><objectgroup name="Objects"><object id="1" x="8" y="8"><polygon points="{}"/></object></objectgroup></map>"#,
+            points
+        );
+        Map::new(map.as_bytes(), &tileset())
+    };
+
+    assert!(polygon("").is_err());
+    assert!(polygon("   ").is_err());
+    let map = polygon("0,0 16,0 0,16").unwrap();
+    assert!(map.get_objects()[0].get_center().x.is_finite());
+}
+
+fn saved_conversation() -> (World, Save) {
+    let mut world = world();
+    let shepherd = map().get_named_point("shepherd").unwrap();
+    world.player.entity.position = Point2::new(shepherd.x + 10.0, shepherd.y);
+    world.player.entity.action = Action::Idle(Direction::Left);
+
+    let dialogtree = world.get_dialogtree().unwrap();
+    let mut conversation = Conversation::new(dialogtree, &mut world.gamestate).unwrap();
+    conversation.next_response();
+    let save = world.get_save(Some(conversation.save()));
+
+    let json = serde_json::to_string(&save).unwrap();
+    (world, serde_json::from_str(&json).unwrap())
+}
+
+#[test]
+fn saves_restore_the_player_and_the_conversation() {
+    let (saved, save) = saved_conversation();
+
+    let mut world = world();
+    world.player.entity.position = Point2::new(0.0, 0.0);
+    let dialogtree = world
+        .give_save(&mut Directory::new(RESOURCES), &save)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(world.player.entity.position, saved.player.entity.position);
+    assert_eq!(world.player.entity.action, Action::Idle(Direction::Left));
+    assert_eq!(world.get_save(save.dialog.clone()), save);
+
+    let conversation =
+        Conversation::load(dialogtree, save.dialog.as_ref().unwrap(), &world.gamestate).unwrap();
+    assert_eq!(conversation.get_speaker(), "Tityrus");
+    assert_eq!(conversation.get_selected_response(), 1);
+}
+
+#[test]
+fn saves_must_match_the_maps_npcs() {
+    let (_, save) = saved_conversation();
+    let id = save.npcs.keys().next().unwrap().clone();
+
+    let mut missing = save.clone();
+    let state = missing.npcs.remove(&id).unwrap();
+    let mut world = world();
+    match world.give_save(&mut Directory::new(RESOURCES), &missing) {
+        Err(Error::MissingSaveState(missing)) => assert_eq!(missing, id),
+        _ => panic!("a missing npc state should be an error"),
+    }
+    assert_eq!(
+        world.player.get_position(),
+        self::world().player.get_position()
+    );
+
+    let mut unknown = save;
+    unknown.npcs.insert("object:999".to_string(), state);
+    match world.give_save(&mut Directory::new(RESOURCES), &unknown) {
+        Err(Error::UnknownSaveState(unknown)) => assert_eq!(unknown, "object:999"),
+        _ => panic!("an unknown npc state should be an error"),
+    }
+}